@@ -0,0 +1,601 @@
+use std::str::FromStr;
+
+use carapax::{
+    handler,
+    methods::{AnswerCallbackQuery, EditMessageText, SendMessage},
+    types::{
+        CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton,
+        ReplyKeyboardMarkup,
+    },
+    Api,
+};
+use chrono::NaiveTime;
+
+use crate::bot_logic::language::Language;
+use crate::bot_logic::menu::{Callback, MainMenuQuestion};
+use crate::bot_logic::strings::{Strings, NOTIFICATION_TIME_PRESETS};
+use crate::bot_logic::telegram_tool::{
+    answer_callback_query, edit_message, send_document, send_message,
+};
+use crate::bot_logic::{
+    chat_language, dates_by_day_to_message, is_group, may_configure, notification_time_message,
+    only_subscribed, set_dialogue_state, short_date, storage_error_message, Context, States,
+};
+use crate::calendar;
+use crate::storage::Storage;
+use crate::trash_dates::today;
+use crate::trash_types::{TrashTypeInfo, REGISTRY};
+
+fn button(label: impl Into<String>, callback: Callback) -> InlineKeyboardButton {
+    InlineKeyboardButton::with_callback_data(label.into(), callback.data())
+}
+
+fn on_off(enabled: bool, text: &Strings) -> &'static str {
+    if enabled {
+        text.state_on
+    } else {
+        text.state_off
+    }
+}
+
+/// A single button leading back to the main menu.
+fn back_markup(text: &Strings) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::from(vec![vec![button(text.menu_back, Callback::MainMenu)]])
+}
+
+/// The main menu, the notification button shows whether notifications are on.
+pub(super) async fn main_menu(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    text: &Strings,
+) -> InlineKeyboardMarkup {
+    use MainMenuQuestion::*;
+
+    let notifications = match storage.get_notification_status(telegram_chat_id).await {
+        Ok(enabled) => format!("{}: {}", text.menu_notification, on_off(enabled, text)),
+        Err(_) => String::from(text.menu_notification),
+    };
+    let entry = |question: MainMenuQuestion| button(question.label(text), Callback::Menu(question));
+
+    InlineKeyboardMarkup::from(vec![
+        vec![entry(ManualRequestTomorrow), entry(RequestNextTwoWeeks)],
+        vec![entry(RequestNextPerType)],
+        vec![
+            button(notifications, Callback::Menu(ToggleNotifications)),
+            entry(SetNotificationTime),
+        ],
+        vec![entry(SelectTrashTypes), entry(ExportCalendar)],
+        vec![entry(Search), entry(RemoveAddress)],
+        vec![entry(SetLanguage), entry(RequestData)],
+        vec![entry(Delete)],
+    ])
+}
+
+/// Lists the trash types with their bin and description, one button per trash type shows
+/// whether it's subscribed, pressing it toggles the type.
+async fn trash_type_menu(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    text: &Strings,
+) -> (String, InlineKeyboardMarkup) {
+    let unsubscribed = match storage.get_unsubscribed_trash_types(telegram_chat_id).await {
+        Ok(unsubscribed) => unsubscribed,
+        Err(e) => {
+            log::error!("Could not get unsubscribed trash types: {}", e);
+            return (String::from(text.message_error_request), back_markup(text));
+        }
+    };
+    let trash_types: Vec<TrashTypeInfo> = REGISTRY
+        .all()
+        .into_iter()
+        .filter(|trash_type| Callback::ToggleTrashType(trash_type.name.clone()).fits_button())
+        .collect();
+
+    let message = format!(
+        "{}\n\n{}",
+        trash_types
+            .iter()
+            .map(|trash_type| trash_type.details(text))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        text.message_ask_toggle_trash_type
+    );
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = trash_types
+        .into_iter()
+        .map(|trash_type| {
+            vec![button(
+                format!(
+                    "{}: {}",
+                    trash_type.label(text),
+                    on_off(!unsubscribed.contains(&trash_type.name), text)
+                ),
+                Callback::ToggleTrashType(trash_type.name),
+            )]
+        })
+        .collect();
+    rows.push(vec![button(text.menu_back, Callback::MainMenu)]);
+
+    (message, InlineKeyboardMarkup::from(rows))
+}
+
+async fn tomorrow_message(storage: &dyn Storage, telegram_chat_id: i64, text: &Strings) -> String {
+    match only_subscribed(
+        storage,
+        telegram_chat_id,
+        storage.get_tomorrows_trash(telegram_chat_id).await,
+    )
+    .await
+    {
+        Ok(dates) if dates.is_empty() => String::from(text.message_no_trash_tomorrow),
+        Ok(dates) => {
+            String::from(text.message_trash_tomorrow)
+                + &dates
+                    .iter()
+                    .map(|date| date.label(text))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+        }
+        Err(e) => {
+            log::error!(
+                "Could not get tomorrows trash dates for manual user request: {}",
+                e
+            );
+            String::from(storage_error_message(&e, text.message_error_request, text))
+        }
+    }
+}
+
+/// The dates of the next `days` days including today, one line per day.
+async fn next_days_message(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    days: i64,
+    title: &str,
+    nothing: &str,
+    text: &Strings,
+) -> String {
+    let from = today();
+
+    match only_subscribed(
+        storage,
+        telegram_chat_id,
+        storage
+            .get_trash_between(
+                telegram_chat_id,
+                from,
+                from + chrono::Duration::days(days - 1),
+            )
+            .await,
+    )
+    .await
+    {
+        Ok(dates) if dates.is_empty() => String::from(nothing),
+        Ok(dates) => String::from(title) + &dates_by_day_to_message(&dates, text),
+        Err(e) => {
+            log::error!("Could not get trash dates of the next {} days: {}", days, e);
+            String::from(storage_error_message(&e, text.message_error_request, text))
+        }
+    }
+}
+
+pub(super) async fn next_week_message(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    text: &Strings,
+) -> String {
+    next_days_message(
+        storage,
+        telegram_chat_id,
+        7,
+        text.message_trash_next_week,
+        text.message_no_trash_next_week,
+        text,
+    )
+    .await
+}
+
+async fn next_two_weeks_message(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    text: &Strings,
+) -> String {
+    next_days_message(
+        storage,
+        telegram_chat_id,
+        14,
+        text.message_trash_next_two_weeks,
+        text.message_no_trash_next_two_weeks,
+        text,
+    )
+    .await
+}
+
+pub(super) async fn next_per_type_message(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    text: &Strings,
+) -> String {
+    match only_subscribed(
+        storage,
+        telegram_chat_id,
+        storage.get_next_per_type(telegram_chat_id).await,
+    )
+    .await
+    {
+        Ok(dates) if dates.is_empty() => String::from(text.message_no_next_pickups),
+        Ok(dates) => {
+            String::from(text.message_next_pickups)
+                + &dates
+                    .iter()
+                    .map(|date| format!("{}: {}", date.label(text), short_date(date.date, text)))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+        }
+        Err(e) => {
+            log::error!("Could not get next trash date per type: {}", e);
+            String::from(storage_error_message(&e, text.message_error_request, text))
+        }
+    }
+}
+
+pub(super) async fn user_data_message(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    text: &Strings,
+) -> String {
+    match storage.get_my_user_data(telegram_chat_id).await {
+        Ok(user_data) => user_data
+            .iter()
+            .map(|(a, b)| a.to_owned() + ": " + b)
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Err(e) => {
+            log::error!("failed requesting user data: {}", e);
+            String::from(storage_error_message(
+                &e,
+                text.message_error_request_user_data,
+                text,
+            ))
+        }
+    }
+}
+
+/// Asks whether the address should be entered manually or found by sharing the location. The
+/// location can only be requested with a reply keyboard, and Telegram rejects such a button in
+/// groups, there the address has to be entered.
+pub(super) async fn ask_search_mode(api: Api, telegram_chat_id: i64, group: bool, text: &Strings) {
+    let (question, rows) = if group {
+        (
+            text.message_ask_search_mode_group,
+            vec![vec![KeyboardButton::new(text.menu_enter_manually)]],
+        )
+    } else {
+        (
+            text.message_ask_search_mode,
+            vec![
+                vec![KeyboardButton::new(text.menu_enter_manually)],
+                vec![KeyboardButton::new(text.menu_find_automatically).request_location()],
+            ],
+        )
+    };
+    let markup = ReplyKeyboardMarkup::from(rows)
+        .one_time_keyboard(true)
+        .resize_keyboard(true);
+
+    send_message(
+        api,
+        SendMessage::new(telegram_chat_id, question).reply_markup(markup),
+    )
+    .await;
+}
+
+pub(super) fn delete_markup(text: &Strings) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::from(vec![vec![
+        button(text.yes, Callback::Delete { confirmed: true }),
+        button(text.no, Callback::Delete { confirmed: false }),
+    ]])
+}
+
+/// Handles the buttons of the inline keyboards. Results replace the message the button belongs
+/// to, questions that need a typed answer hand over to the dialogue.
+#[handler]
+pub(super) async fn handle_callback(context: &Context, query: CallbackQuery) {
+    let api = context.api.clone();
+    let storage = context.storage.as_ref();
+
+    let (message, callback) = match (query.message, query.data.as_deref().map(Callback::from_str)) {
+        (Some(message), Some(Ok(callback))) => (message, callback),
+        (_, data) => {
+            log::warn!("Ignoring callback query with data {:?}", data);
+            answer_callback_query(api, AnswerCallbackQuery::new(query.id)).await;
+            return;
+        }
+    };
+    let chat_id = message.get_chat_id();
+    let language = chat_language(storage, chat_id, query.from.language_code.as_deref()).await;
+    let text = language.strings();
+
+    if callback.changes_settings()
+        && !may_configure(&api, chat_id, is_group(&message), Some(query.from.id)).await
+    {
+        log::info!("Group member without admin rights tried to change settings.");
+        answer_callback_query(
+            api,
+            AnswerCallbackQuery::new(query.id)
+                .text(text.message_only_admins)
+                .show_alert(true),
+        )
+        .await;
+        return;
+    }
+
+    // A button cancels a running dialogue, only questions that need a typed answer start one.
+    let next_state = match callback {
+        Callback::Menu(MainMenuQuestion::Search) => States::Search,
+        Callback::Menu(MainMenuQuestion::SetNotificationTime) => States::SetNotificationTime,
+        _ => States::Start,
+    };
+    set_dialogue_state(context, chat_id, query.from.id, &next_state).await;
+
+    log::info!("Handling callback {:?}", callback);
+
+    let mut notice: Option<&'static str> = None;
+    let edit: Option<(String, InlineKeyboardMarkup)> = match callback {
+        Callback::MainMenu => Some((
+            String::from(text.message_ask_what_user_wants),
+            main_menu(storage, chat_id, text).await,
+        )),
+        Callback::Menu(MainMenuQuestion::ManualRequestTomorrow) => Some((
+            tomorrow_message(storage, chat_id, text).await,
+            back_markup(text),
+        )),
+        Callback::Menu(MainMenuQuestion::RequestNextTwoWeeks) => Some((
+            next_two_weeks_message(storage, chat_id, text).await,
+            back_markup(text),
+        )),
+        Callback::Menu(MainMenuQuestion::RequestNextPerType) => Some((
+            next_per_type_message(storage, chat_id, text).await,
+            back_markup(text),
+        )),
+        Callback::Menu(MainMenuQuestion::RequestData) => Some((
+            user_data_message(storage, chat_id, text).await,
+            back_markup(text),
+        )),
+        Callback::Menu(MainMenuQuestion::Search) => {
+            ask_search_mode(api.clone(), chat_id, is_group(&message), text).await;
+            None
+        }
+        Callback::Menu(MainMenuQuestion::ToggleNotifications) => {
+            match storage.get_notification_status(chat_id).await {
+                Ok(enabled) => match storage.set_notification(chat_id, !enabled).await {
+                    Ok(enabled) => {
+                        notice = Some(if enabled {
+                            text.message_notifications_activated
+                        } else {
+                            text.message_notifications_deactivated
+                        });
+                        Some((
+                            String::from(text.message_ask_what_user_wants),
+                            main_menu(storage, chat_id, text).await,
+                        ))
+                    }
+                    Err(e) => {
+                        log::error!("error while changing notification status: {}", e);
+                        notice = Some(text.message_error_change_notification);
+                        None
+                    }
+                },
+                Err(e) => {
+                    log::error!("{}", e);
+                    notice = Some(text.message_change_notification_negative);
+                    None
+                }
+            }
+        }
+        Callback::Menu(MainMenuQuestion::SetNotificationTime) => Some((
+            String::from(text.message_ask_notification_time),
+            InlineKeyboardMarkup::from(vec![
+                NOTIFICATION_TIME_PRESETS
+                    .iter()
+                    .filter_map(|time| NaiveTime::parse_from_str(time, "%H:%M").ok())
+                    .map(|time| {
+                        button(
+                            time.format("%H:%M").to_string(),
+                            Callback::NotificationTime(time),
+                        )
+                    })
+                    .collect(),
+                vec![button(text.menu_back, Callback::MainMenu)],
+            ]),
+        )),
+        Callback::Menu(MainMenuQuestion::SelectTrashTypes) => {
+            Some(trash_type_menu(storage, chat_id, text).await)
+        }
+        Callback::Menu(MainMenuQuestion::SetLanguage) => Some((
+            String::from(text.message_ask_language),
+            InlineKeyboardMarkup::from(vec![
+                Language::ALL
+                    .iter()
+                    .map(|language| button(language.name(), Callback::Language(*language)))
+                    .collect(),
+                vec![button(text.menu_back, Callback::MainMenu)],
+            ]),
+        )),
+        Callback::Menu(MainMenuQuestion::RemoveAddress) => {
+            match storage.get_addresses(chat_id).await {
+                Ok(names) if names.is_empty() => {
+                    Some((String::from(text.message_no_addresses), back_markup(text)))
+                }
+                Ok(names) => {
+                    let mut rows: Vec<Vec<InlineKeyboardButton>> = names
+                        .into_iter()
+                        .map(|name| (name.clone(), Callback::RemoveAddress(name)))
+                        .filter(|(name, callback)| {
+                            let fits = callback.fits_button();
+                            if !fits {
+                                log::warn!("Address name '{}' is too long for a button.", name);
+                            }
+                            fits
+                        })
+                        .map(|(name, callback)| vec![button(name, callback)])
+                        .collect();
+                    rows.push(vec![button(text.menu_back, Callback::MainMenu)]);
+
+                    Some((
+                        String::from(text.message_ask_remove_address),
+                        InlineKeyboardMarkup::from(rows),
+                    ))
+                }
+                Err(e) => {
+                    log::error!("Could not get saved addresses: {}", e);
+                    Some((
+                        String::from(storage_error_message(&e, text.message_error_request, text)),
+                        back_markup(text),
+                    ))
+                }
+            }
+        }
+        Callback::Menu(MainMenuQuestion::Delete) => {
+            Some((String::from(text.deletion), delete_markup(text)))
+        }
+        Callback::Menu(MainMenuQuestion::ExportCalendar) => Some((
+            String::from(text.message_ask_calendar_reminder),
+            InlineKeyboardMarkup::from(vec![vec![
+                button(text.yes, Callback::ExportCalendar { reminder: true }),
+                button(text.no, Callback::ExportCalendar { reminder: false }),
+            ]]),
+        )),
+        Callback::ToggleTrashType(trash_type) => {
+            let toggled = match storage.get_unsubscribed_trash_types(chat_id).await {
+                Ok(unsubscribed) => {
+                    let subscribe = unsubscribed.contains(&trash_type);
+                    storage
+                        .set_trash_type_subscription(chat_id, trash_type, subscribe)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+
+            match toggled {
+                Ok(_) => Some(trash_type_menu(storage, chat_id, text).await),
+                Err(e) => {
+                    log::error!("Could not change trash type subscription: {}", e);
+                    notice = Some(text.message_error_trash_types);
+                    None
+                }
+            }
+        }
+        Callback::NotificationTime(notification_time) => {
+            match storage
+                .set_notification_time(chat_id, notification_time)
+                .await
+            {
+                Ok(notification_time) => Some((
+                    notification_time_message(notification_time, text),
+                    back_markup(text),
+                )),
+                Err(e) => {
+                    log::error!("error while changing notification time: {}", e);
+                    Some((
+                        String::from(storage_error_message(
+                            &e,
+                            text.message_error_notification_time,
+                            text,
+                        )),
+                        back_markup(text),
+                    ))
+                }
+            }
+        }
+        Callback::Language(language) => {
+            match storage
+                .set_language(chat_id, language.code().to_string())
+                .await
+            {
+                Ok(_) => Some((
+                    String::from(language.strings().message_language_set),
+                    back_markup(language.strings()),
+                )),
+                Err(e) => {
+                    log::error!("Could not change language: {}", e);
+                    Some((String::from(text.message_error_language), back_markup(text)))
+                }
+            }
+        }
+        Callback::RemoveAddress(name) => {
+            let result = match storage.remove_address(chat_id, name).await {
+                Ok(true) => text.message_address_removed,
+                Ok(false) => text.message_address_not_found,
+                Err(e) => {
+                    log::error!("Could not remove address: {}", e);
+                    storage_error_message(&e, text.message_error_request, text)
+                }
+            };
+            Some((String::from(result), back_markup(text)))
+        }
+        Callback::Delete { confirmed: true } => {
+            let result = match storage.remove_user_data(chat_id).await {
+                Ok(true) => text.message_deleted,
+                _ => text.no_delete_msg,
+            };
+            Some((String::from(result), back_markup(text)))
+        }
+        Callback::Delete { confirmed: false } => Some((
+            String::from(text.message_nothing_happens),
+            back_markup(text),
+        )),
+        Callback::ExportCalendar { reminder } => {
+            match only_subscribed(storage, chat_id, storage.get_upcoming_trash(chat_id).await).await
+            {
+                Ok(dates) if dates.is_empty() => Some((
+                    String::from(text.message_no_upcoming_trash),
+                    back_markup(text),
+                )),
+                Ok(dates) => {
+                    send_document(
+                        api.clone(),
+                        chat_id,
+                        text.calendar_file_name,
+                        calendar::to_ics(
+                            chat_id,
+                            &dates,
+                            Some(chrono::Duration::hours(8)).filter(|_| reminder),
+                            text,
+                        )
+                        .into_bytes(),
+                    )
+                    .await;
+                    Some((
+                        String::from(text.message_ask_what_user_wants),
+                        main_menu(storage, chat_id, text).await,
+                    ))
+                }
+                Err(e) => {
+                    log::error!("Could not get upcoming trash dates for calendar: {}", e);
+                    Some((
+                        String::from(storage_error_message(&e, text.message_error_request, text)),
+                        back_markup(text),
+                    ))
+                }
+            }
+        }
+    };
+
+    if let Some((content, markup)) = edit {
+        edit_message(
+            api.clone(),
+            EditMessageText::new(chat_id, message.id, content).reply_markup(markup),
+        )
+        .await;
+    }
+
+    let answer = AnswerCallbackQuery::new(query.id);
+    answer_callback_query(
+        api,
+        match notice {
+            Some(notice) => answer.text(notice),
+            None => answer,
+        },
+    )
+    .await;
+}
@@ -0,0 +1,44 @@
+use carapax::{
+    handler,
+    types::{ChatMember, Update, UpdateKind},
+    HandlerResult,
+};
+
+use crate::bot_logic::{disable_notifications, Context};
+
+/// Follows the membership of the bot in a chat (`my_chat_member` updates). Notifications are
+/// turned off when a user blocks the bot or it's removed from a group, and turned on again when
+/// the bot is unblocked or added back, unless the user had turned them off before. Chats that
+/// aren't registered are left alone.
+#[handler]
+pub(super) async fn handle_chat_member(context: &Context, update: Update) -> HandlerResult {
+    let member_update = match update.kind {
+        UpdateKind::BotStatus(member_update) => member_update,
+        _ => return HandlerResult::Continue,
+    };
+    let chat_id = member_update.chat.get_id();
+
+    match member_update.new_chat_member {
+        ChatMember::Kicked(_) | ChatMember::Left(_) => {
+            log::info!("Bot was blocked or removed in chat {}.", chat_id);
+            disable_notifications(context.storage.as_ref(), chat_id).await;
+        }
+        _ => {
+            if matches!(
+                member_update.old_chat_member,
+                ChatMember::Kicked(_) | ChatMember::Left(_)
+            ) {
+                log::info!("Bot was unblocked or added back in chat {}.", chat_id);
+                match context.storage.set_unreachable(chat_id, false).await {
+                    Ok(true) => log::info!("Enabled notifications of chat {} again.", chat_id),
+                    Ok(false) => {}
+                    Err(e) => {
+                        log::error!("Could not enable notifications of chat {}: {}", chat_id, e)
+                    }
+                }
+            }
+        }
+    }
+
+    HandlerResult::Stop
+}
@@ -0,0 +1,262 @@
+use std::str::FromStr;
+
+use carapax::{
+    handler,
+    methods::{SendMessage, SetMyCommands},
+    types::{BotCommand, Command},
+    Api, HandlerResult,
+};
+
+use crate::bot_logic::callback::{
+    ask_search_mode, delete_markup, next_per_type_message, next_week_message, user_data_message,
+};
+use crate::bot_logic::language::Language;
+use crate::bot_logic::menu::{AdminCommand, SlashCommand};
+use crate::bot_logic::statistics::day_bounds;
+use crate::bot_logic::strings::Strings;
+use crate::bot_logic::telegram_tool::send_message;
+use crate::bot_logic::{
+    chat_language, is_addressed_to_bot, is_group, may_configure, set_dialogue_state, Context,
+    States,
+};
+use crate::storage::StorageError;
+use crate::trash_dates::today;
+
+/// Registers the commands with Telegram, so clients can suggest them. The descriptions are in
+/// the default language.
+pub(super) async fn register_commands(api: &Api) {
+    let text = Language::default().strings();
+    let commands = SlashCommand::ALL
+        .iter()
+        .map(|command| BotCommand::new(command.name(), command.description(text)))
+        .collect::<Result<Vec<BotCommand>, _>>();
+
+    match commands {
+        Ok(commands) => match api.execute(SetMyCommands::new(commands)).await {
+            Ok(_) => log::info!("Registered bot commands."),
+            Err(e) => log::warn!("Could not register bot commands: {}", e),
+        },
+        Err(e) => log::error!("Invalid bot command: {}", e),
+    }
+}
+
+fn help_message(text: &Strings) -> String {
+    let mut lines = vec![String::from(text.message_help)];
+    lines.extend(
+        SlashCommand::ALL
+            .iter()
+            .map(|command| format!("/{} - {}", command.name(), command.description(text))),
+    );
+
+    lines.join("\n")
+}
+
+async fn admin_message(context: &Context, command: AdminCommand, text: &Strings) -> String {
+    match command {
+        AdminCommand::Stats => {
+            let (from, to) = day_bounds(today().pred());
+            let mut lines = match context.storage.get_statistics().await {
+                Ok(statistics) => vec![
+                    format!("{}: {}", text.admin_users, statistics.users),
+                    format!(
+                        "{}: {}",
+                        text.admin_notifications_enabled, statistics.notifications_enabled
+                    ),
+                ],
+                Err(e) => {
+                    log::error!("Could not get user statistics: {}", e);
+                    vec![String::from(text.message_error_request)]
+                }
+            };
+            match context.storage.get_reminder_counts(from, to).await {
+                Ok(yesterday) => {
+                    lines.push(format!(
+                        "{}: {}",
+                        text.admin_reminders_sent_yesterday, yesterday.sent
+                    ));
+                    lines.push(format!(
+                        "{}: {}",
+                        text.admin_reminders_failed_yesterday, yesterday.failed
+                    ));
+                }
+                Err(e) => log::error!("Could not get reminder statistics: {}", e),
+            }
+            lines.push(format!(
+                "{}: {}",
+                text.admin_last_scheduler_run,
+                context
+                    .statistics
+                    .last_run()
+                    .map(|run| run.format("%d.%m.%Y %H:%M").to_string())
+                    .unwrap_or_else(|| String::from(text.admin_never))
+            ));
+
+            lines.join("\n")
+        }
+        AdminCommand::Streets => match context.storage.get_statistics().await {
+            Ok(statistics) => {
+                String::from(text.admin_users_per_street)
+                    + &statistics
+                        .users_per_street
+                        .iter()
+                        .map(|(street, users)| format!("{}: {}", street, users))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+            }
+            Err(e) => {
+                log::error!("Could not get user statistics: {}", e);
+                String::from(text.message_error_request)
+            }
+        },
+    }
+}
+
+/// Answers the admin commands in admin chats, everywhere else they are dropped without an
+/// answer.
+async fn handle_admin_command(context: &Context, command: &Command, admin_command: AdminCommand) {
+    let message = command.get_message();
+    let chat_id = message.get_chat_id();
+    if !context.admin_chat_ids.contains(&chat_id) {
+        log::debug!(
+            "Ignoring admin command {:?} in chat {}",
+            admin_command,
+            chat_id
+        );
+        return;
+    }
+
+    log::info!("Handling admin command {:?}", admin_command);
+    let language = chat_language(
+        context.storage.as_ref(),
+        chat_id,
+        message
+            .get_user()
+            .and_then(|user| user.language_code.as_deref()),
+    )
+    .await;
+    let reply = admin_message(context, admin_command, language.strings()).await;
+
+    send_message(context.api.clone(), SendMessage::new(chat_id, reply)).await;
+}
+
+/// Answers the slash commands directly, other commands like `/start` are left to the dialogue.
+/// A command cancels a running dialogue, `/address` starts the address search.
+#[handler]
+pub(super) async fn handle_command(context: &Context, command: Command) -> HandlerResult {
+    let message = command.get_message();
+    if let Ok(admin_command) = AdminCommand::from_str(command.get_name()) {
+        handle_admin_command(context, &command, admin_command).await;
+        return HandlerResult::Stop;
+    }
+    let slash_command = match SlashCommand::from_str(command.get_name()) {
+        Ok(slash_command) => slash_command,
+        Err(_) => return HandlerResult::Continue,
+    };
+    if is_group(message) && !is_addressed_to_bot(message, &context.bot_username) {
+        return HandlerResult::Continue;
+    }
+
+    let api = context.api.clone();
+    let storage = context.storage.as_ref();
+    let chat_id = message.get_chat_id();
+    let user = message.get_user();
+    let language = chat_language(
+        storage,
+        chat_id,
+        user.and_then(|user| user.language_code.as_deref()),
+    )
+    .await;
+    let text = language.strings();
+
+    if slash_command.changes_settings()
+        && !may_configure(&api, chat_id, is_group(message), user.map(|user| user.id)).await
+    {
+        log::info!("Group member without admin rights tried to change settings.");
+        send_message(api, SendMessage::new(chat_id, text.message_only_admins)).await;
+        return HandlerResult::Stop;
+    }
+
+    if let Some(user) = user {
+        let next_state = match slash_command {
+            SlashCommand::Address => States::Search,
+            _ => States::Start,
+        };
+        set_dialogue_state(context, chat_id, user.id, &next_state).await;
+    }
+
+    log::info!("Handling command {:?}", slash_command);
+
+    match slash_command {
+        SlashCommand::Next => {
+            send_message(
+                api,
+                SendMessage::new(chat_id, next_per_type_message(storage, chat_id, text).await),
+            )
+            .await
+        }
+        SlashCommand::Week => {
+            send_message(
+                api,
+                SendMessage::new(chat_id, next_week_message(storage, chat_id, text).await),
+            )
+            .await
+        }
+        SlashCommand::Address => ask_search_mode(api, chat_id, is_group(message), text).await,
+        SlashCommand::Notify => {
+            let enable = match command.get_args().first().map(|arg| arg.to_lowercase()) {
+                Some(arg) if arg == "on" => Some(true),
+                Some(arg) if arg == "off" => Some(false),
+                _ => None,
+            };
+
+            let reply = match enable {
+                Some(enable) => match storage.set_notification(chat_id, enable).await {
+                    Ok(true) => String::from(text.message_notifications_activated),
+                    Ok(false) => String::from(text.message_notifications_deactivated),
+                    Err(e) => match StorageError::of(&e) {
+                        Some(StorageError::NotFound(_)) => {
+                            String::from(text.message_change_notification_negative)
+                        }
+                        _ => {
+                            log::error!("error while changing notification status: {}", e);
+                            String::from(text.message_error_change_notification)
+                        }
+                    },
+                },
+                None => match storage.get_notification_status(chat_id).await {
+                    Ok(enabled) => format!(
+                        "{}: {}\n{}",
+                        text.menu_notification,
+                        if enabled {
+                            text.state_on
+                        } else {
+                            text.state_off
+                        },
+                        text.message_notify_usage
+                    ),
+                    Err(_) => String::from(text.message_notify_usage),
+                },
+            };
+            send_message(api, SendMessage::new(chat_id, reply)).await
+        }
+        SlashCommand::Delete => {
+            send_message(
+                api,
+                SendMessage::new(chat_id, text.deletion).reply_markup(delete_markup(text)),
+            )
+            .await
+        }
+        SlashCommand::MyData => {
+            send_message(
+                api,
+                SendMessage::new(chat_id, user_data_message(storage, chat_id, text).await),
+            )
+            .await
+        }
+        SlashCommand::Help => {
+            send_message(api, SendMessage::new(chat_id, help_message(text))).await
+        }
+    };
+
+    HandlerResult::Stop
+}
@@ -0,0 +1,50 @@
+use crate::bot_logic::strings::{Strings, ENGLISH, GERMAN};
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Language {
+    German,
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::German, Language::English];
+
+    /// Picks the language for an IETF language tag, e.g. Telegram's `language_code`. German for
+    /// German tags, English for everything else.
+    pub fn from_code(code: &str) -> Self {
+        if code.to_lowercase().starts_with("de") {
+            Language::German
+        } else {
+            Language::English
+        }
+    }
+
+    /// The code the language is stored with.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::German => "de",
+            Language::English => "en",
+        }
+    }
+
+    /// Name of the language in the language itself, used as button label.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::German => "Deutsch",
+            Language::English => "English",
+        }
+    }
+
+    pub fn strings(&self) -> &'static Strings {
+        match self {
+            Language::German => &GERMAN,
+            Language::English => &ENGLISH,
+        }
+    }
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Language::German
+    }
+}
@@ -1,88 +1,324 @@
-use std::fmt::Formatter;
 use std::str::FromStr;
 
+use chrono::NaiveTime;
+
+use crate::bot_logic::language::Language;
+use crate::bot_logic::strings::Strings;
+
+#[derive(Clone, Copy)]
 pub enum LocationQuestion {
     Correct,
     NumberFalse,
     AllFalse,
 }
 
-const CORRECT: &str = "Ja, beides stimmt!";
-const NUMBER_FALSE: &str = "Nein, die Hausnummer stimmt nicht!";
-const ALL_FALSE: &str = "Nein, beides ist falsch!";
+impl LocationQuestion {
+    const ALL: [LocationQuestion; 3] = [
+        LocationQuestion::Correct,
+        LocationQuestion::NumberFalse,
+        LocationQuestion::AllFalse,
+    ];
 
-impl std::fmt::Display for LocationQuestion {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    pub fn label(&self, strings: &Strings) -> &'static str {
         match self {
-            self::LocationQuestion::Correct => write!(f, "{}", CORRECT),
-            self::LocationQuestion::NumberFalse => {
-                write!(f, "{}", NUMBER_FALSE)
-            }
-            self::LocationQuestion::AllFalse => write!(f, "{}", ALL_FALSE),
+            self::LocationQuestion::Correct => strings.location_correct,
+            self::LocationQuestion::NumberFalse => strings.location_number_false,
+            self::LocationQuestion::AllFalse => strings.location_all_false,
         }
     }
 }
 
+/// Accepts the labels of every language, the user may have changed the language while the
+/// keyboard was shown.
 impl FromStr for LocationQuestion {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            CORRECT => Ok(Self::Correct),
-            NUMBER_FALSE => Ok(Self::NumberFalse),
-            ALL_FALSE => Ok(Self::AllFalse),
-            _ => Err(format!("Could not convert to LocationQuestion: {}", s)),
-        }
+        Language::ALL
+            .iter()
+            .flat_map(|language| {
+                LocationQuestion::ALL
+                    .iter()
+                    .filter(move |question| question.label(language.strings()) == s)
+            })
+            .next()
+            .copied()
+            .ok_or_else(|| format!("Could not convert to LocationQuestion: {}", s))
     }
 }
 
+#[derive(Clone, Copy, Debug)]
 pub enum MainMenuQuestion {
     ManualRequestTomorrow,
+    RequestNextTwoWeeks,
+    RequestNextPerType,
     Search,
+    RemoveAddress,
+    SelectTrashTypes,
     ToggleNotifications,
+    SetNotificationTime,
+    SetLanguage,
     Delete,
     RequestData,
+    ExportCalendar,
 }
 
-const MANUAL_REQUEST_TOMORROW: &str = "Manuelle Abfrage";
-const SEARCH: &str = "Straße auswählen/ändern";
-const NOTIFICATION: &str = "Benachrichtigungen ein-/ausschalten";
-const DELETE: &str = "Alle Daten löschen";
-const REQUEST_DATA: &str = "Gespeicherte Daten abfragen";
+impl MainMenuQuestion {
+    const ALL: [MainMenuQuestion; 12] = [
+        MainMenuQuestion::ManualRequestTomorrow,
+        MainMenuQuestion::RequestNextTwoWeeks,
+        MainMenuQuestion::RequestNextPerType,
+        MainMenuQuestion::Search,
+        MainMenuQuestion::RemoveAddress,
+        MainMenuQuestion::SelectTrashTypes,
+        MainMenuQuestion::ToggleNotifications,
+        MainMenuQuestion::SetNotificationTime,
+        MainMenuQuestion::SetLanguage,
+        MainMenuQuestion::Delete,
+        MainMenuQuestion::RequestData,
+        MainMenuQuestion::ExportCalendar,
+    ];
 
-impl std::fmt::Display for MainMenuQuestion {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    pub fn label(&self, strings: &Strings) -> &'static str {
         match self {
-            self::MainMenuQuestion::ManualRequestTomorrow => {
-                write!(f, "{}", MANUAL_REQUEST_TOMORROW)
-            }
-            self::MainMenuQuestion::Search => {
-                write!(f, "{}", SEARCH)
+            self::MainMenuQuestion::ManualRequestTomorrow => strings.menu_manual_request_tomorrow,
+            self::MainMenuQuestion::RequestNextTwoWeeks => strings.menu_request_next_two_weeks,
+            self::MainMenuQuestion::RequestNextPerType => strings.menu_request_next_per_type,
+            self::MainMenuQuestion::Search => strings.menu_search,
+            self::MainMenuQuestion::RemoveAddress => strings.menu_remove_address,
+            self::MainMenuQuestion::SelectTrashTypes => strings.menu_select_trash_types,
+            self::MainMenuQuestion::ToggleNotifications => strings.menu_notification,
+            self::MainMenuQuestion::SetNotificationTime => strings.menu_notification_time,
+            self::MainMenuQuestion::SetLanguage => strings.menu_set_language,
+            self::MainMenuQuestion::Delete => strings.menu_delete,
+            self::MainMenuQuestion::RequestData => strings.menu_request_data,
+            self::MainMenuQuestion::ExportCalendar => strings.menu_export_calendar,
+        }
+    }
+
+    /// Language independent name used in the callback data of the menu buttons.
+    pub fn key(&self) -> &'static str {
+        match self {
+            self::MainMenuQuestion::ManualRequestTomorrow => "tomorrow",
+            self::MainMenuQuestion::RequestNextTwoWeeks => "two_weeks",
+            self::MainMenuQuestion::RequestNextPerType => "per_type",
+            self::MainMenuQuestion::Search => "search",
+            self::MainMenuQuestion::RemoveAddress => "remove_address",
+            self::MainMenuQuestion::SelectTrashTypes => "trash_types",
+            self::MainMenuQuestion::ToggleNotifications => "notifications",
+            self::MainMenuQuestion::SetNotificationTime => "notification_time",
+            self::MainMenuQuestion::SetLanguage => "language",
+            self::MainMenuQuestion::Delete => "delete",
+            self::MainMenuQuestion::RequestData => "data",
+            self::MainMenuQuestion::ExportCalendar => "calendar",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        MainMenuQuestion::ALL
+            .iter()
+            .find(|question| question.key() == key)
+            .copied()
+    }
+
+    /// Entries that change the stored address or settings, in groups only admins may use them.
+    pub fn changes_settings(&self) -> bool {
+        matches!(
+            self,
+            MainMenuQuestion::Search
+                | MainMenuQuestion::RemoveAddress
+                | MainMenuQuestion::SelectTrashTypes
+                | MainMenuQuestion::ToggleNotifications
+                | MainMenuQuestion::SetNotificationTime
+                | MainMenuQuestion::SetLanguage
+                | MainMenuQuestion::Delete
+        )
+    }
+}
+
+/// Telegram rejects buttons with more than 64 bytes of callback data.
+pub const MAX_CALLBACK_DATA_LENGTH: usize = 64;
+
+/// What an inline keyboard button does. The callback data carries everything needed to handle
+/// the button, so buttons keep working no matter which messages were sent in between.
+#[derive(Clone, Debug)]
+pub enum Callback {
+    MainMenu,
+    Menu(MainMenuQuestion),
+    ToggleTrashType(String),
+    NotificationTime(NaiveTime),
+    Language(Language),
+    RemoveAddress(String),
+    ExportCalendar { reminder: bool },
+    Delete { confirmed: bool },
+}
+
+impl Callback {
+    pub fn data(&self) -> String {
+        match self {
+            Callback::MainMenu => String::from("menu"),
+            Callback::Menu(question) => format!("menu:{}", question.key()),
+            Callback::ToggleTrashType(trash_type) => format!("trash:{}", trash_type),
+            Callback::NotificationTime(time) => format!("time:{}", time.format("%H:%M")),
+            Callback::Language(language) => format!("lang:{}", language.code()),
+            Callback::RemoveAddress(name) => format!("address:{}", name),
+            Callback::ExportCalendar { reminder } => {
+                format!("calendar:{}", if *reminder { "reminder" } else { "plain" })
             }
-            self::MainMenuQuestion::ToggleNotifications => {
-                write!(f, "{}", NOTIFICATION)
+            Callback::Delete { confirmed } => {
+                format!("delete:{}", if *confirmed { "yes" } else { "no" })
             }
-            self::MainMenuQuestion::Delete => {
-                write!(f, "{}", DELETE)
+        }
+    }
+
+    pub fn fits_button(&self) -> bool {
+        self.data().len() <= MAX_CALLBACK_DATA_LENGTH
+    }
+
+    /// Buttons that change the stored address or settings, in groups only admins may use them.
+    pub fn changes_settings(&self) -> bool {
+        match self {
+            Callback::MainMenu | Callback::ExportCalendar { .. } => false,
+            Callback::Menu(question) => question.changes_settings(),
+            _ => true,
+        }
+    }
+}
+
+impl FromStr for Callback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s.split_once(':').unwrap_or((s, ""));
+
+        match (kind, value) {
+            ("menu", "") => Some(Callback::MainMenu),
+            ("menu", key) => MainMenuQuestion::from_key(key).map(Callback::Menu),
+            ("trash", trash_type) if !trash_type.is_empty() => {
+                Some(Callback::ToggleTrashType(trash_type.to_string()))
             }
-            self::MainMenuQuestion::RequestData => {
-                write!(f, "{}", REQUEST_DATA)
+            ("time", time) => NaiveTime::parse_from_str(time, "%H:%M")
+                .ok()
+                .map(Callback::NotificationTime),
+            ("lang", code) => Language::ALL
+                .iter()
+                .find(|language| language.code() == code)
+                .copied()
+                .map(Callback::Language),
+            ("address", name) if !name.is_empty() => {
+                Some(Callback::RemoveAddress(name.to_string()))
             }
+            ("calendar", "reminder") => Some(Callback::ExportCalendar { reminder: true }),
+            ("calendar", "plain") => Some(Callback::ExportCalendar { reminder: false }),
+            ("delete", "yes") => Some(Callback::Delete { confirmed: true }),
+            ("delete", "no") => Some(Callback::Delete { confirmed: false }),
+            _ => None,
         }
+        .ok_or_else(|| format!("Could not convert to Callback: {}", s))
     }
 }
 
-impl FromStr for MainMenuQuestion {
-    type Err = &'static str;
+/// Slash commands offered next to the menu, registered with Telegram at startup.
+#[derive(Clone, Copy, Debug)]
+pub enum SlashCommand {
+    Next,
+    Week,
+    Address,
+    Notify,
+    Delete,
+    MyData,
+    Help,
+}
+
+impl SlashCommand {
+    pub const ALL: [SlashCommand; 7] = [
+        SlashCommand::Next,
+        SlashCommand::Week,
+        SlashCommand::Address,
+        SlashCommand::Notify,
+        SlashCommand::Delete,
+        SlashCommand::MyData,
+        SlashCommand::Help,
+    ];
+
+    /// Name of the command without the leading slash.
+    pub fn name(&self) -> &'static str {
+        match self {
+            self::SlashCommand::Next => "next",
+            self::SlashCommand::Week => "week",
+            self::SlashCommand::Address => "address",
+            self::SlashCommand::Notify => "notify",
+            self::SlashCommand::Delete => "delete",
+            self::SlashCommand::MyData => "mydata",
+            self::SlashCommand::Help => "help",
+        }
+    }
+
+    pub fn description(&self, strings: &Strings) -> &'static str {
+        match self {
+            self::SlashCommand::Next => strings.command_next,
+            self::SlashCommand::Week => strings.command_week,
+            self::SlashCommand::Address => strings.command_address,
+            self::SlashCommand::Notify => strings.command_notify,
+            self::SlashCommand::Delete => strings.command_delete,
+            self::SlashCommand::MyData => strings.command_mydata,
+            self::SlashCommand::Help => strings.command_help,
+        }
+    }
+
+    /// Commands that change the stored address or settings, in groups only admins may use them.
+    pub fn changes_settings(&self) -> bool {
+        matches!(
+            self,
+            SlashCommand::Address | SlashCommand::Notify | SlashCommand::Delete
+        )
+    }
+}
+
+/// Accepts command names with or without the leading slash and bot name, e.g. `/next@bot`.
+impl FromStr for SlashCommand {
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            MANUAL_REQUEST_TOMORROW => Ok(MainMenuQuestion::ManualRequestTomorrow),
-            SEARCH => Ok(MainMenuQuestion::Search),
-            NOTIFICATION => Ok(MainMenuQuestion::ToggleNotifications),
-            DELETE => Ok(MainMenuQuestion::Delete),
-            REQUEST_DATA => Ok(MainMenuQuestion::RequestData),
-            _ => Err("Could not convert to MainMenuQuestion."),
+        let name = s.trim_start_matches('/').split('@').next().unwrap_or("");
+
+        SlashCommand::ALL
+            .iter()
+            .find(|command| command.name().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| format!("Could not convert to SlashCommand: {}", s))
+    }
+}
+
+/// Commands only answered in the chats configured as admin chats. They are not registered with
+/// Telegram, other chats don't get to see them.
+#[derive(Clone, Copy, Debug)]
+pub enum AdminCommand {
+    Stats,
+    Streets,
+}
+
+impl AdminCommand {
+    const ALL: [AdminCommand; 2] = [AdminCommand::Stats, AdminCommand::Streets];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            self::AdminCommand::Stats => "stats",
+            self::AdminCommand::Streets => "streets",
         }
     }
 }
+
+impl FromStr for AdminCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim_start_matches('/').split('@').next().unwrap_or("");
+
+        AdminCommand::ALL
+            .iter()
+            .find(|command| command.name().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| format!("Could not convert to AdminCommand: {}", s))
+    }
+}
@@ -1,6 +1,7 @@
 use std::convert::Infallible;
 use std::env;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Error;
@@ -10,40 +11,66 @@ use carapax::{
         DialogueResult::{self, Exit, Next},
         State,
     },
-    longpoll::LongPoll,
-    methods::SendMessage,
+    methods::{GetChatMember, GetMe, GetUpdates, SendMessage},
     ratelimit::{limit_all_chats, nonzero, KeyedRateLimitHandler},
-    session::{backend::fs::FilesystemBackend, SessionManager},
+    session::{backend::fs::FilesystemBackend, Session, SessionId, SessionManager},
     types::{
-        KeyboardButton, Message,
+        Chat, ChatMember, KeyboardButton, Message,
         MessageData::{Location, Text},
         ParseMode::Markdown,
         ReplyKeyboardMarkup,
     },
-    Api, Dispatcher,
+    Api, Dispatcher, UpdateHandler,
 };
-use serde::{Deserialize, Serialize};
-use tempfile::tempdir;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::bot_logic::language::Language;
+use crate::bot_logic::statistics::ReminderStatistics;
+use crate::bot_logic::strings::Strings;
+use crate::bot_logic::telegram_tool::{send_message, try_send_message, SendError};
+use crate::health::Health;
+use crate::location_lookup::cache::GeocodingCache;
+use crate::location_lookup::{
+    geocoder, LocationCandidate, LocationLookup, Lookup, LookupError, LOOKUP_TIMEOUT,
+};
+use crate::metrics;
+use crate::storage::{self, Storage, StorageError};
+use crate::trash_dates::{Street, TrashDate};
+use crate::trash_types::REGISTRY;
+use crate::webhook;
+
+mod callback;
+mod chat_member;
+mod command;
+mod language;
+mod menu;
+mod statistics;
+pub(crate) mod strings;
 
-use crate::bot_logic::telegram_tool::send_message;
-use crate::location_lookup::{LocationLookup, LocationResult, Lookup};
-use crate::trash_dates::{RequestPerformer, Street};
+/// Version of the stored dialogue states. Increase it whenever a change to [`States`] changes
+/// the meaning of already stored states, sessions with another version restart the dialogue.
+const STATES_VERSION: u32 = 2;
 
-mod menu;
-mod strings;
+/// Unique dialogue name, the dialogue state is stored in the session under this key.
+const DIALOGUE_NAME: &str = "BasicDialogue";
 
-#[derive(Serialize, Deserialize)]
+/// The dialogue only covers questions that need a typed answer or a shared location, everything
+/// else is handled by the inline keyboards in [`callback`].
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(remote = "Self")]
 enum States {
     Start,
-    MainMenu,
     Search,
     SearchManually,
     SearchManuallyKeyboard,
     SearchManuallyHouseNumber,
     SearchManuallyHouseNumberKeyboard,
     SearchAskIfOk,
-    Remove,
+    AskAddressName,
+    SetNotificationTime,
 }
 
 impl State for States {
@@ -52,103 +79,517 @@ impl State for States {
     }
 }
 
+/// Dialogue state as it's stored in the session, tagged with [`STATES_VERSION`].
+#[derive(Serialize, Deserialize)]
+struct StoredState {
+    version: u32,
+    state: serde_json::Value,
+}
+
+impl Serialize for States {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredState {
+            version: STATES_VERSION,
+            state: States::serialize(self, serde_json::value::Serializer)
+                .map_err(ser::Error::custom)?,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for States {
+    /// Never fails for valid json: states of another version, unversioned states and unknown
+    /// variants fall back to [`States::Start`] instead of breaking the session.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+
+        match serde_json::from_value::<StoredState>(value) {
+            Ok(stored) if stored.version == STATES_VERSION => Ok(States::deserialize(stored.state)
+                .unwrap_or_else(|e| {
+                    log::warn!("Unknown stored dialogue state, restarting dialogue: {}", e);
+                    States::Start
+                })),
+            Ok(stored) => {
+                log::info!(
+                    "Stored dialogue state has version {}, expected {}, restarting dialogue.",
+                    stored.version,
+                    STATES_VERSION
+                );
+                Ok(States::Start)
+            }
+            Err(e) => {
+                log::info!("Unversioned dialogue state, restarting dialogue: {}", e);
+                Ok(States::Start)
+            }
+        }
+    }
+}
+
 pub struct Bot;
 
 struct Context {
     api: Api,
     session_manager: SessionManager<FilesystemBackend>,
-    request_performer: RequestPerformer,
+    storage: Arc<dyn Storage>,
     sender: mpsc::Sender<Lookup>,
+    bot_username: String,
+    admin_chat_ids: Vec<i64>,
+    statistics: Arc<ReminderStatistics>,
+}
+
+/// Chats allowed to use the admin commands, read from the comma separated `ADMIN_CHAT_IDS`.
+fn admin_chat_ids_from_env() -> Vec<i64> {
+    env::var("ADMIN_CHAT_IDS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .filter_map(|id| match id.parse() {
+            Ok(id) => Some(id),
+            Err(e) => {
+                log::error!("Ignoring invalid admin chat id '{}': {}", id, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The language chosen for the chat, otherwise the Telegram language of the sender.
+async fn chat_language(
+    storage: &dyn Storage,
+    telegram_chat_id: i64,
+    user_language_code: Option<&str>,
+) -> Language {
+    match storage.get_language(telegram_chat_id).await {
+        Ok(Some(code)) => return Language::from_code(&code),
+        Ok(None) => {}
+        Err(e) => log::debug!("Could not get chat language: {}", e),
+    }
+
+    user_language_code
+        .map(Language::from_code)
+        .unwrap_or_default()
+}
+
+fn is_group(message: &Message) -> bool {
+    matches!(message.chat, Chat::Group(_) | Chat::Supergroup(_))
+}
+
+/// Whether a group message is meant for the bot: a command without a bot name or with this
+/// bot's name, or a message mentioning the bot.
+fn is_addressed_to_bot(message: &Message, bot_username: &str) -> bool {
+    let text = match message.get_text() {
+        Some(text) => &text.data,
+        None => return false,
+    };
+    let mention = format!("@{}", bot_username);
+
+    match text.strip_prefix('/') {
+        Some(command) => match command
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .split_once('@')
+        {
+            Some((_, name)) => name.eq_ignore_ascii_case(bot_username),
+            None => true,
+        },
+        None => text.to_lowercase().contains(&mention.to_lowercase()),
+    }
+}
+
+/// Only administrators may change the settings of a group, everyone may change the settings
+/// of a private chat.
+async fn may_configure(
+    api: &Api,
+    telegram_chat_id: i64,
+    group: bool,
+    user_id: Option<i64>,
+) -> bool {
+    if !group {
+        return true;
+    }
+
+    let user_id = match user_id {
+        Some(user_id) => user_id,
+        None => return false,
+    };
+
+    match api
+        .execute(GetChatMember::new(telegram_chat_id, user_id))
+        .await
+    {
+        Ok(ChatMember::Creator(_)) | Ok(ChatMember::Administrator(_)) => true,
+        Ok(_) => false,
+        Err(e) => {
+            log::warn!("Could not get chat member status: {}", e);
+            false
+        }
+    }
+}
+
+/// Stores a session value. Failures are only logged, the dialogue asks again for values that
+/// are missing later on.
+async fn set_session_value<V: Serialize + Sync>(
+    session: &mut Session<FilesystemBackend>,
+    key: &str,
+    value: &V,
+) {
+    if let Err(e) = session.set(key, value).await {
+        log::error!("Could not store session value '{}': {}", key, e);
+    }
+}
+
+/// Replaces the dialogue state of a user in a chat, e.g. to cancel a running dialogue or to
+/// start one from a button or command.
+async fn set_dialogue_state(
+    context: &Context,
+    telegram_chat_id: i64,
+    user_id: i64,
+    state: &States,
+) {
+    match context
+        .session_manager
+        .get_session(SessionId::new(telegram_chat_id, user_id))
+    {
+        Ok(mut session) => set_session_value(&mut session, DIALOGUE_NAME, state).await,
+        Err(e) => log::error!("Could not get session: {}", e),
+    }
+}
+
+/// Reads a session value, missing and unreadable values are `None`.
+async fn get_session_value<V: de::DeserializeOwned>(
+    session: &mut Session<FilesystemBackend>,
+    key: &str,
+) -> Option<V> {
+    session.get(key).await.unwrap_or_else(|e| {
+        log::warn!("Could not read session value '{}': {}", key, e);
+        None
+    })
 }
 
 async fn get_reverse_location(
     location: &carapax::types::Location,
     sender: &mpsc::Sender<Lookup>,
-) -> Result<LocationResult, Error> {
+) -> Result<Vec<LocationCandidate>, Error> {
     let (location_result_sender, location_result_answer) =
-        tokio::sync::oneshot::channel::<Result<Option<LocationResult>, Error>>();
+        tokio::sync::oneshot::channel::<Result<Vec<LocationCandidate>, Error>>();
+    let deadline = Instant::now() + LOOKUP_TIMEOUT;
 
+    metrics::GEOCODING_QUEUE_DEPTH.inc();
     sender
         .send(Lookup {
             longitude: location.longitude,
             latitude: location.latitude,
+            deadline,
             responder: location_result_sender,
         })
-        .await?;
+        .await
+        .map_err(|e| {
+            metrics::GEOCODING_QUEUE_DEPTH.dec();
+            e
+        })?;
+
+    match tokio::time::timeout_at(deadline, location_result_answer).await {
+        Err(_) => Err(Error::from(LookupError::Timeout)),
+        Ok(Err(_)) => Err(Error::from(LookupError::Stopped)),
+        Ok(Ok(result)) => {
+            let candidates = result?;
+            if candidates.is_empty() {
+                return Err(Error::from(LookupError::NotFound));
+            }
+            Ok(candidates)
+        }
+    }
+}
+
+/// Keeps the candidates whose street is in the database, together with the street id. A street
+/// that can't be resolved only drops its own candidates, the error is returned if none is left.
+async fn resolve_candidate_streets(
+    storage: &dyn Storage,
+    candidates: Vec<LocationCandidate>,
+) -> Result<Vec<(i64, LocationCandidate)>, Error> {
+    let mut street_ids: Vec<(String, Option<i64>)> = Vec::new();
+    let mut last_error = None;
+    let mut resolved = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let street = &candidate.location.street;
+        let street_id = match street_ids.iter().find(|(name, _)| name == street) {
+            Some((_, street_id)) => *street_id,
+            None => {
+                let street_id = match storage.get_street_id(street.clone()).await {
+                    Ok(street_id) => Some(street_id),
+                    Err(e) => {
+                        log::warn!("Could not find street '{}' of candidate: {}", street, e);
+                        last_error = Some(e);
+                        None
+                    }
+                };
+                street_ids.push((street.clone(), street_id));
+                street_id
+            }
+        };
+        if let Some(street_id) = street_id {
+            resolved.push((street_id, candidate));
+        }
+    }
+
+    match last_error {
+        Some(e) if resolved.is_empty() => Err(e),
+        _ => Ok(resolved),
+    }
+}
+
+/// Asks for the name the new address is saved under, the existing names are offered as buttons
+/// to overwrite one of them.
+async fn ask_address_name(storage: &dyn Storage, api: Api, telegram_chat_id: i64, text: &Strings) {
+    let mut names = storage
+        .get_addresses(telegram_chat_id)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Could not get saved addresses: {}", e);
+            Vec::new()
+        });
+    if !names.iter().any(|name| name == text.default_address_name) {
+        names.insert(0, String::from(text.default_address_name));
+    }
 
-    Ok(location_result_answer.await??.unwrap())
+    send_message(
+        api,
+        SendMessage::new(telegram_chat_id, text.message_ask_address_name).reply_markup(
+            ReplyKeyboardMarkup::from_vec(
+                names
+                    .into_iter()
+                    .map(|name| vec![KeyboardButton::new(name)])
+                    .collect(),
+            )
+            .resize_keyboard(true)
+            .one_time_keyboard(true),
+        ),
+    )
+    .await;
 }
 
-async fn add_user(
-    request_performer: &RequestPerformer,
+async fn add_address(
+    storage: &dyn Storage,
     api: Api,
     telegram_chat_id: i64,
+    name: String,
     street: Option<i64>,
     house_number: Option<String>,
+    text: &Strings,
 ) {
-    use crate::bot_logic::strings::*;
-
     log::info!("User entered the house correctly, updating user profile.");
 
     send_message(
         api.clone(),
-        SendMessage::new(telegram_chat_id, MESSAGE_SAVE_LOCATION),
+        SendMessage::new(telegram_chat_id, text.message_save_location),
     )
     .await;
 
-    match request_performer
-        .add_user(telegram_chat_id, street, house_number)
-        .await
-    {
+    let result = match street {
+        Some(street) => {
+            storage
+                .add_address(telegram_chat_id, name, street, house_number)
+                .await
+        }
+        None => Err(Error::msg("no street selected")),
+    };
+
+    match result {
         Ok(_) => {
             send_message(
                 api,
-                SendMessage::new(telegram_chat_id, MESSAGE_CONFIRM_ADDRESS_ADDED),
+                SendMessage::new(telegram_chat_id, text.message_confirm_address_added),
             )
             .await
         }
-        Err(_) => {
+        Err(e) => {
+            log::error!("Could not add address: {}", e);
             send_message(
                 api,
-                SendMessage::new(telegram_chat_id, MESSAGE_ERROR_ADDRESS_ADDED),
+                SendMessage::new(
+                    telegram_chat_id,
+                    storage_error_message(&e, text.message_error_address_added, text),
+                ),
             )
             .await
         }
     };
 }
 
-async fn send_affirmative_or_negative(
-    api: Api,
+/// The message telling the user why a storage request failed: `not_found` if the requested data
+/// doesn't exist, a hint to try again later if the storage is temporarily unavailable.
+fn storage_error_message(error: &Error, not_found: &'static str, text: &Strings) -> &'static str {
+    match StorageError::of(error) {
+        Some(StorageError::NotFound(_)) => not_found,
+        Some(e) if e.is_temporary() => text.message_service_unavailable,
+        _ => text.message_error_request,
+    }
+}
+
+/// Drops the dates of trash types the chat has unsubscribed from.
+async fn only_subscribed(
+    storage: &dyn Storage,
     telegram_chat_id: i64,
-    switch: bool,
-    positive_message: &str,
-    negative_message: &str,
-) {
-    match switch {
-        true => send_message(api, SendMessage::new(telegram_chat_id, positive_message)).await,
-        false => send_message(api, SendMessage::new(telegram_chat_id, negative_message)).await,
-    };
+    dates: Result<Vec<TrashDate>, Error>,
+) -> Result<Vec<TrashDate>, Error> {
+    let unsubscribed = storage
+        .get_unsubscribed_trash_types(telegram_chat_id)
+        .await?;
+
+    Ok(dates?
+        .into_iter()
+        .filter(|date| !unsubscribed.contains(&date.name))
+        .collect())
+}
+
+/// Turns off the reminders of a chat that can't be reached anymore, e.g. because the user
+/// blocked the bot. Only reminders turned off this way are turned on again when the bot is
+/// unblocked, see [`chat_member::handle_chat_member`].
+async fn disable_notifications(storage: &dyn Storage, telegram_chat_id: i64) {
+    match storage.set_unreachable(telegram_chat_id, true).await {
+        Ok(false) => {}
+        Ok(true) => log::info!(
+            "Disabled notifications of unreachable chat {}.",
+            telegram_chat_id
+        ),
+        Err(e) => log::error!(
+            "Could not disable notifications of unreachable chat {}: {}",
+            telegram_chat_id,
+            e
+        ),
+    }
+}
+
+/// Counts a reminder that could not be sent in the statistics.
+async fn record_failed_notification(storage: &dyn Storage, telegram_chat_id: i64, date: NaiveDate) {
+    if let Err(e) = storage
+        .record_failed_notification(telegram_chat_id, date)
+        .await
+    {
+        log::warn!(
+            "Could not record failed reminder of chat {}: {}",
+            telegram_chat_id,
+            e
+        );
+    }
 }
 
 mod telegram_tool {
+    use std::io::Cursor;
+    use std::time::Duration;
+
     use backoff::future::retry;
-    use backoff::Error::Transient;
+    use backoff::Error::{Permanent, Transient};
     use backoff::ExponentialBackoff;
-    use carapax::methods::SendMessage;
-    use carapax::Api;
+    use carapax::methods::{
+        AnswerCallbackQuery, EditMessageText, Method, SendDocument, SendMessage,
+    };
+    use carapax::types::{InputFileReader, ResponseError};
+    use carapax::{Api, ExecuteError};
+
+    use crate::metrics;
+
+    /// Why a message could not be sent.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SendError {
+        /// The bot was blocked or removed from the group, the chat doesn't exist anymore or the
+        /// user deactivated their account. Sending to the chat again won't succeed.
+        Unreachable,
+        /// Telegram rejected the message, or still failed after all retries.
+        Failed,
+    }
 
-    pub async fn send_message(api: Api, to_send: SendMessage) {
+    /// Errors that are caused by the chat, not by the message.
+    fn is_unreachable(error: &ResponseError) -> bool {
+        match error.error_code() {
+            Some(403) => true,
+            Some(400) => error.description().contains("chat not found"),
+            _ => false,
+        }
+    }
+
+    /// Executes the method built by `method`, retrying with backoff on network errors, server
+    /// errors and rate limits. A rate limited request waits the time Telegram asks for first.
+    /// Other errors are not retried.
+    async fn execute<M, F>(api: &Api, method: F) -> Result<M::Response, SendError>
+    where
+        M: Method,
+        F: Fn() -> M,
+    {
         retry(ExponentialBackoff::default(), || async {
-            api.execute(to_send.clone()).await.map_err(Transient)
+            match api.execute(method()).await {
+                Ok(response) => Ok(response),
+                Err(ExecuteError::Response(e)) => {
+                    if let Some(seconds) = e.retry_after() {
+                        log::warn!("Rate limited by telegram, retrying in {}s.", seconds);
+                        tokio::time::sleep(Duration::from_secs(seconds)).await;
+                        Err(Transient(ExecuteError::Response(e)))
+                    } else if e.error_code().map_or(false, |code| code >= 500) {
+                        Err(Transient(ExecuteError::Response(e)))
+                    } else {
+                        Err(Permanent(ExecuteError::Response(e)))
+                    }
+                }
+                Err(e) => Err(Transient(e)),
+            }
         })
         .await
-        .map_err(|e| {
-            log::error!(
-                "Error while sending telegram message after multiple retries: {}",
-                e
+        .map_err(|e| match e {
+            ExecuteError::Response(e) if is_unreachable(&e) => {
+                log::info!("Chat can't be reached anymore: {}", e);
+                SendError::Unreachable
+            }
+            e => {
+                log::error!("Error while executing telegram request: {}", e);
+                SendError::Failed
+            }
+        })
+    }
+
+    /// Sends a message, returns why it could not be sent on failure.
+    pub async fn try_send_message(api: Api, to_send: SendMessage) -> Result<(), SendError> {
+        let result = execute(&api, || to_send.clone()).await.map(|_| ());
+
+        if result.is_ok() {
+            metrics::TELEGRAM_MESSAGES_SENT.inc();
+        } else {
+            metrics::TELEGRAM_MESSAGES_FAILED.inc();
+        }
+        result
+    }
+
+    /// Sends a message, failures are only logged.
+    pub async fn send_message(api: Api, to_send: SendMessage) {
+        try_send_message(api, to_send).await.ok();
+    }
+
+    /// Sends a file, failures are only logged.
+    pub async fn send_document(api: Api, chat_id: i64, file_name: &str, content: Vec<u8>) {
+        execute(&api, || {
+            SendDocument::new(
+                chat_id,
+                InputFileReader::new(Cursor::new(content.clone())).info(file_name),
             )
         })
-        .unwrap();
+        .await
+        .ok();
+    }
+
+    /// Edits are not retried, Telegram rejects edits that don't change the message anyway.
+    pub async fn edit_message(api: Api, to_edit: EditMessageText) {
+        if let Err(e) = api.execute(to_edit).await {
+            log::warn!("Could not edit telegram message: {}", e);
+        }
+    }
+
+    /// Stops the loading indicator of the pressed button, the answer expires after a few seconds
+    /// so it's not retried.
+    pub async fn answer_callback_query(api: Api, answer: AnswerCallbackQuery) {
+        if let Err(e) = api.execute(answer).await {
+            log::warn!("Could not answer callback query: {}", e);
+        }
     }
 }
 
@@ -159,18 +600,41 @@ async fn bot_dialogue(
     input: Message,
 ) -> Result<DialogueResult<States>, Infallible> {
     use self::menu::*;
-    use self::strings::*;
     use self::States::*;
     use telegram_tool::send_message;
 
     let chat_id = input.get_chat_id();
-    let user = input.get_user().unwrap();
-    let first_name = user.first_name.clone();
-    let mut session = context.session_manager.get_session(&input).unwrap();
+    let group = is_group(&input);
+    let language = chat_language(
+        context.storage.as_ref(),
+        chat_id,
+        input
+            .get_user()
+            .and_then(|user| user.language_code.as_deref()),
+    )
+    .await;
+    let text = language.strings();
+    let first_name = match input.get_user() {
+        Some(user) if !group => user.first_name.clone(),
+        _ => String::new(),
+    };
+
+    // In groups the dialogue is only started by messages meant for the bot, a running dialogue
+    // still gets the answers of the user who started it.
+    if group && matches!(state, Start) && !is_addressed_to_bot(&input, &context.bot_username) {
+        return Ok(Exit);
+    }
+    let mut session = match context.session_manager.get_session(&input) {
+        Ok(session) => session,
+        Err(e) => {
+            log::error!("Could not get session: {}", e);
+            return Ok(Exit);
+        }
+    };
     let api = context.api.clone();
 
     #[allow(clippy::eval_order_dependence)]
-    Ok(match state {
+    let result = match state {
         Start => {
             send_message(
                 context.api.clone(),
@@ -178,33 +642,17 @@ async fn bot_dialogue(
                     chat_id,
                     format!(
                         "{}{}{}!\n{}",
-                        HELLO,
+                        text.hello,
                         if !first_name.is_empty() { " " } else { "" },
                         first_name,
-                        MESSAGE_ASK_WHAT_USER_WANTS
+                        text.message_ask_what_user_wants
                     ),
                 )
-                .reply_markup(
-                    ReplyKeyboardMarkup::from(vec![
-                        vec![
-                            KeyboardButton::new(MainMenuQuestion::Search.to_string()),
-                            KeyboardButton::new(MainMenuQuestion::ToggleNotifications.to_string()),
-                            KeyboardButton::new(
-                                MainMenuQuestion::ManualRequestTomorrow.to_string(),
-                            ),
-                        ],
-                        vec![
-                            KeyboardButton::new(MainMenuQuestion::Delete.to_string()),
-                            KeyboardButton::new(MainMenuQuestion::RequestData.to_string()),
-                        ],
-                    ])
-                    .one_time_keyboard(false)
-                    .resize_keyboard(false),
-                ),
+                .reply_markup(callback::main_menu(context.storage.as_ref(), chat_id, text).await),
             )
             .await;
 
-            Next(MainMenu)
+            Exit
         }
         Search => {
             log::info!("Handling search dialog.");
@@ -219,50 +667,71 @@ async fn bot_dialogue(
 
                             send_message(
                                 api,
-                                SendMessage::new(chat_id, MESSAGE_ASK_FOR_MANUAL_ENTRY),
+                                SendMessage::new(
+                                    chat_id,
+                                    match LookupError::of(&e) {
+                                        Some(LookupError::Timeout) => {
+                                            text.message_location_lookup_timeout
+                                        }
+                                        _ => text.message_ask_for_manual_entry,
+                                    },
+                                ),
                             )
                             .await;
 
                             Next(SearchManually)
                         }
-                        Ok(location_result) => {
-                            session.set("location", &location_result).await.unwrap();
-
-                            match context
-                                .request_performer
-                                .get_street_id(location_result.street.clone())
+                        Ok(candidates) => {
+                            match resolve_candidate_streets(context.storage.as_ref(), candidates)
                                 .await
                             {
-                                Ok(street_id) => {
-                                    session.set("street_id", &street_id).await.unwrap();
-                                    session
-                                        .set("street_number", &location_result.house_number)
-                                        .await
-                                        .unwrap();
+                                Ok(candidates) => {
+                                    let location_result = candidates[0].1.location.clone();
+                                    set_session_value(
+                                        &mut session,
+                                        "location_candidates",
+                                        &candidates,
+                                    )
+                                    .await;
+
+                                    // The nearest address first, the other candidates can be
+                                    // tapped if its house number is wrong.
+                                    let mut reply_keyboard_rows = vec![vec![KeyboardButton::new(
+                                        LocationQuestion::Correct.label(text),
+                                    )]];
+                                    for (_, candidate) in &candidates[1..] {
+                                        reply_keyboard_rows
+                                            .push(vec![KeyboardButton::new(candidate.label())]);
+                                    }
+                                    reply_keyboard_rows.push(vec![KeyboardButton::new(
+                                        LocationQuestion::NumberFalse.label(text),
+                                    )]);
+                                    reply_keyboard_rows.push(vec![KeyboardButton::new(
+                                        LocationQuestion::AllFalse.label(text),
+                                    )]);
 
                                     send_message(
                                         api,
                                         SendMessage::new(
                                             chat_id,
-                                            format!(
-                                                "{} *{}*",
-                                                CONFIRM_STREET_AND_NUMBER, location_result
-                                            ),
+                                            if candidates.len() > 1 {
+                                                format!(
+                                                    "{} *{}*\n{}",
+                                                    text.confirm_street_and_number,
+                                                    location_result,
+                                                    text.location_other_candidates
+                                                )
+                                            } else {
+                                                format!(
+                                                    "{} *{}*",
+                                                    text.confirm_street_and_number, location_result
+                                                )
+                                            },
                                         )
                                         .reply_markup(
-                                            ReplyKeyboardMarkup::from(vec![
-                                                vec![KeyboardButton::new(
-                                                    LocationQuestion::Correct.to_string(),
-                                                )],
-                                                vec![KeyboardButton::new(
-                                                    LocationQuestion::NumberFalse.to_string(),
-                                                )],
-                                                vec![KeyboardButton::new(
-                                                    LocationQuestion::AllFalse.to_string(),
-                                                )],
-                                            ])
-                                            .one_time_keyboard(true)
-                                            .resize_keyboard(true),
+                                            ReplyKeyboardMarkup::from_vec(reply_keyboard_rows)
+                                                .one_time_keyboard(true)
+                                                .resize_keyboard(true),
                                         )
                                         .parse_mode(Markdown),
                                     )
@@ -271,11 +740,18 @@ async fn bot_dialogue(
                                     Next(SearchAskIfOk)
                                 }
                                 Err(e) => {
-                                    log::error!("{}", e);
+                                    log::error!("Could not find street of location: {}", e);
 
                                     send_message(
                                         api,
-                                        SendMessage::new(chat_id, MESSAGE_SEARCH_COULD_NOT_FIND),
+                                        SendMessage::new(
+                                            chat_id,
+                                            storage_error_message(
+                                                &e,
+                                                text.message_search_could_not_find,
+                                                text,
+                                            ),
+                                        ),
                                     )
                                     .await;
 
@@ -286,69 +762,74 @@ async fn bot_dialogue(
                     }
                 }
                 Text(_) => {
-                    send_message(api, SendMessage::new(chat_id, MESSAGE_ENTER_STREET_NAME)).await;
+                    send_message(
+                        api,
+                        SendMessage::new(chat_id, text.message_enter_street_name),
+                    )
+                    .await;
                     Next(SearchManually)
                 }
                 _ => Next(Start),
             }
         }
         SearchManually => match input.data {
-            Text(t) => {
-                match context
-                    .request_performer
-                    .search_similar_streets(t.data)
-                    .await
-                {
-                    Ok(search_results) => {
-                        session.set("street_search", &search_results).await.unwrap();
+            Text(t) => match context.storage.search_similar_streets(t.data).await {
+                Ok(search_results) => {
+                    set_session_value(&mut session, "street_search", &search_results).await;
 
-                        send_message(api, {
-                            let mut reply_keyboard_rows: Vec<Vec<KeyboardButton>> =
-                                Vec::with_capacity(search_results.len());
+                    send_message(api, {
+                        let mut reply_keyboard_rows: Vec<Vec<KeyboardButton>> =
+                            Vec::with_capacity(search_results.len());
 
-                            for street in search_results {
-                                reply_keyboard_rows.push(vec![KeyboardButton::new(street.street)]);
-                            }
-                            reply_keyboard_rows
-                                .push(vec![KeyboardButton::new(MENU_NO_STREET_CORRECT)]);
-
-                            SendMessage::new(chat_id, MESSAGE_CONFIRM_ONE_OF_THE_STREETS)
-                                .reply_markup(
-                                    ReplyKeyboardMarkup::from_vec(reply_keyboard_rows)
-                                        .resize_keyboard(true)
-                                        .one_time_keyboard(true),
-                                )
-                        })
-                        .await;
+                        for street in search_results {
+                            reply_keyboard_rows.push(vec![KeyboardButton::new(street.street)]);
+                        }
+                        reply_keyboard_rows
+                            .push(vec![KeyboardButton::new(text.menu_no_street_correct)]);
+
+                        SendMessage::new(chat_id, text.message_confirm_one_of_the_streets)
+                            .reply_markup(
+                                ReplyKeyboardMarkup::from_vec(reply_keyboard_rows)
+                                    .resize_keyboard(true)
+                                    .one_time_keyboard(true),
+                            )
+                    })
+                    .await;
 
-                        Next(SearchManuallyKeyboard)
-                    }
-                    Err(e) => {
-                        log::error!("Finding streets failed: {}", e);
+                    Next(SearchManuallyKeyboard)
+                }
+                Err(e) => {
+                    log::error!("Finding streets failed: {}", e);
 
-                        send_message(api, SendMessage::new(chat_id, MESSAGE_ERROR_STREET_SEARCH))
-                            .await;
+                    send_message(
+                        api,
+                        SendMessage::new(
+                            chat_id,
+                            storage_error_message(&e, text.message_error_request, text),
+                        ),
+                    )
+                    .await;
 
-                        Next(Start)
-                    }
+                    Next(Start)
                 }
-            }
+            },
             _ => Next(Start),
         },
         SearchManuallyKeyboard => match input.data {
             Text(t) => {
                 let mut was_successful = false;
 
-                for street in session
-                    .get::<&str, Vec<Street>>("street_search")
+                for street in get_session_value::<Vec<Street>>(&mut session, "street_search")
                     .await
-                    .unwrap()
-                    .unwrap()
+                    .unwrap_or_default()
                 {
                     if street.street == t.data {
-                        session.set("street_id", &street.id).await.unwrap();
-                        send_message(api.clone(), SendMessage::new(chat_id, HOUSE_NUMBER_MESSAGE))
-                            .await;
+                        set_session_value(&mut session, "street_id", &street.id).await;
+                        send_message(
+                            api.clone(),
+                            SendMessage::new(chat_id, text.house_number_message),
+                        )
+                        .await;
 
                         was_successful = true;
                         break;
@@ -358,7 +839,7 @@ async fn bot_dialogue(
                 if was_successful {
                     Next(SearchManuallyHouseNumber)
                 } else {
-                    send_message(api, SendMessage::new(chat_id, HELP_MESSAGE)).await;
+                    send_message(api, SendMessage::new(chat_id, text.help_message)).await;
 
                     Next(SearchManually)
                 }
@@ -375,13 +856,13 @@ async fn bot_dialogue(
                         chat_id,
                         format!(
                             "{}: {}?\n{}",
-                            HOUSE_NUMBER_QUESTION_1, t.data, HOUSE_NUMBER_QUESTION_2
+                            text.house_number_question_1, t.data, text.house_number_question_2
                         ),
                     )
                     .reply_markup(
                         ReplyKeyboardMarkup::from_vec(vec![
-                            vec![KeyboardButton::new(YES)],
-                            vec![KeyboardButton::new(NO)],
+                            vec![KeyboardButton::new(text.yes)],
+                            vec![KeyboardButton::new(text.no)],
                         ])
                         .resize_keyboard(true)
                         .one_time_keyboard(true),
@@ -389,7 +870,7 @@ async fn bot_dialogue(
                 )
                 .await;
 
-                session.set("street_number", &t.data).await.unwrap();
+                set_session_value(&mut session, "street_number", &t.data).await;
 
                 Next(SearchManuallyHouseNumberKeyboard)
             }
@@ -397,22 +878,14 @@ async fn bot_dialogue(
         },
         SearchManuallyHouseNumberKeyboard => match input.data {
             Text(t) => match &t.data[..] {
-                YES => {
-                    add_user(
-                        &context.request_performer,
-                        api,
-                        chat_id,
-                        session.get("street_id").await.unwrap(),
-                        session.get("street_number").await.unwrap(),
-                    )
-                    .await;
-
-                    Exit
+                answer if answer == text.yes => {
+                    ask_address_name(context.storage.as_ref(), api, chat_id, text).await;
+                    Next(AskAddressName)
                 }
                 _ => {
                     log::info!("User entered the house number wrong, trying again.");
 
-                    send_message(api, SendMessage::new(chat_id, HOUSE_NUMBER_MESSAGE)).await;
+                    send_message(api, SendMessage::new(chat_id, text.house_number_message)).await;
 
                     Next(SearchManuallyHouseNumber)
                 }
@@ -423,299 +896,511 @@ async fn bot_dialogue(
             Text(t) => {
                 log::info!("Found automatic search answer: {}", t.data);
 
-                match LocationQuestion::from_str(&t.data).unwrap() {
-                    LocationQuestion::Correct => {
-                        add_user(
-                            &context.request_performer,
+                let candidates = get_session_value::<Vec<(i64, LocationCandidate)>>(
+                    &mut session,
+                    "location_candidates",
+                )
+                .await
+                .unwrap_or_default();
+                let question = LocationQuestion::from_str(&t.data);
+                let chosen = match question {
+                    Ok(LocationQuestion::Correct) | Ok(LocationQuestion::NumberFalse) => {
+                        candidates.first()
+                    }
+                    _ => candidates
+                        .iter()
+                        .find(|(_, candidate)| candidate.label() == t.data),
+                };
+
+                match chosen {
+                    Some((street_id, candidate)) => {
+                        set_session_value(&mut session, "street_id", street_id).await;
+
+                        match &candidate.location.house_number {
+                            Some(house_number)
+                                if !matches!(question, Ok(LocationQuestion::NumberFalse)) =>
+                            {
+                                set_session_value(&mut session, "street_number", house_number)
+                                    .await;
+
+                                ask_address_name(context.storage.as_ref(), api, chat_id, text)
+                                    .await;
+                                Next(AskAddressName)
+                            }
+                            // Another house number of the nearest street.
+                            Some(_) => {
+                                send_message(
+                                    api,
+                                    SendMessage::new(chat_id, text.message_enter_house_number),
+                                )
+                                .await;
+                                Next(SearchManuallyHouseNumber)
+                            }
+                            // Only the street is known, the dates depend on the house number.
+                            None => {
+                                send_message(
+                                    api,
+                                    SendMessage::new(chat_id, text.house_number_message),
+                                )
+                                .await;
+                                Next(SearchManuallyHouseNumber)
+                            }
+                        }
+                    }
+                    None => {
+                        send_message(
                             api,
-                            chat_id,
-                            session.get("street_id").await.unwrap(),
-                            session.get("street_number").await.unwrap(),
+                            SendMessage::new(chat_id, text.message_enter_street_name),
                         )
                         .await;
-                        Exit
-                    }
-                    LocationQuestion::NumberFalse => {
-                        send_message(api, SendMessage::new(chat_id, MESSAGE_ENTER_HOUSE_NUMBER))
-                            .await;
-                        Next(SearchManuallyHouseNumber)
-                    }
-                    LocationQuestion::AllFalse => {
-                        send_message(api, SendMessage::new(chat_id, MESSAGE_ENTER_STREET_NAME))
-                            .await;
                         Next(SearchManually)
                     }
                 }
             }
             _ => Next(Start),
         },
-        Remove => match input.data {
-            Text(t) => match &t.data[..] {
-                YES => {
-                    let worked = context.request_performer.remove_user_data(chat_id).await;
-
-                    send_affirmative_or_negative(
+        AskAddressName => match input.data {
+            Text(t) if !Callback::RemoveAddress(t.data.trim().to_string()).fits_button() => {
+                send_message(
+                    api,
+                    SendMessage::new(chat_id, text.message_address_name_too_long),
+                )
+                .await;
+                Next(AskAddressName)
+            }
+            Text(t) if !t.data.trim().is_empty() => {
+                add_address(
+                    context.storage.as_ref(),
+                    api,
+                    chat_id,
+                    t.data.trim().to_string(),
+                    get_session_value(&mut session, "street_id").await,
+                    get_session_value(&mut session, "street_number").await,
+                    text,
+                )
+                .await;
+                Exit
+            }
+            _ => {
+                send_message(
+                    api,
+                    SendMessage::new(chat_id, text.message_ask_address_name),
+                )
+                .await;
+                Next(AskAddressName)
+            }
+        },
+        SetNotificationTime => match input.data {
+            Text(t) => match NaiveTime::parse_from_str(t.data.trim(), "%H:%M") {
+                Ok(notification_time) => {
+                    match context
+                        .storage
+                        .set_notification_time(chat_id, notification_time)
+                        .await
+                    {
+                        Ok(notification_time) => {
+                            send_message(
+                                api,
+                                SendMessage::new(
+                                    chat_id,
+                                    notification_time_message(notification_time, text),
+                                ),
+                            )
+                            .await;
+                        }
+                        Err(e) => {
+                            log::error!("error while changing notification time: {}", e);
+                            send_message(
+                                api,
+                                SendMessage::new(
+                                    chat_id,
+                                    storage_error_message(
+                                        &e,
+                                        text.message_error_notification_time,
+                                        text,
+                                    ),
+                                ),
+                            )
+                            .await;
+                        }
+                    };
+                    Exit
+                }
+                Err(_) => {
+                    send_message(
                         api,
-                        chat_id,
-                        worked.unwrap_or(false),
-                        MESSAGE_DELETED,
-                        NO_DELETE_MSG,
+                        SendMessage::new(chat_id, text.message_invalid_notification_time),
                     )
                     .await;
-                    Exit
-                }
-                _ => {
-                    send_message(api, SendMessage::new(chat_id, MESSAGE_NOTHING_HAPPENS)).await;
-                    Exit
+                    Next(SetNotificationTime)
                 }
             },
             _ => Exit,
         },
-        MainMenu => match input.data {
-            Text(t) => match MainMenuQuestion::from_str(&t.data) {
-                Ok(main_menu_question) => match main_menu_question {
-                    MainMenuQuestion::Search => {
-                        log::info!("Starting search dialog.");
-
-                        let row = vec![
-                            vec![KeyboardButton::new(MENU_ENTER_MANUALLY)],
-                            vec![KeyboardButton::new(MENU_FIND_AUTOMATICALLY).request_location()],
-                        ];
+    };
 
-                        let markup = ReplyKeyboardMarkup::from(row)
-                            .one_time_keyboard(true)
-                            .resize_keyboard(true);
+    metrics::DIALOGUE_TRANSITIONS
+        .with_label_values(&[&match &result {
+            Next(state) => format!("{:?}", state),
+            Exit => String::from("Exit"),
+        }])
+        .inc();
 
-                        send_message(
-                            api,
-                            SendMessage::new(chat_id, MESSAGE_ASK_SEARCH_MODE).reply_markup(markup),
-                        )
-                        .await;
+    Ok(result)
+}
 
-                        Next(Search)
-                    }
-                    MainMenuQuestion::ToggleNotifications => {
-                        log::info!("Benachrichtigungen");
+/// The reminder of the pickups on one day with one line per trash type, including bin colour
+/// and description, e.g. "Morgen wird dieser Abfall abgeholt:\n📰 Papier (blau): Zeitungen und
+/// Kartons – Zuhause". Empty if there are no dates.
+fn reminder_message(dates: &[TrashDate], same_day: bool, text: &Strings) -> String {
+    if dates.is_empty() {
+        return String::new();
+    }
 
-                        match context
-                            .request_performer
-                            .get_notification_status(chat_id)
-                            .await
-                        {
-                            Ok(t) => {
-                                match context
-                                    .request_performer
-                                    .set_notification(chat_id, !t)
-                                    .await
-                                {
-                                    Ok(new_state) => {
-                                        send_affirmative_or_negative(
-                                            api,
-                                            chat_id,
-                                            new_state,
-                                            MESSAGE_NOTIFICATIONS_ACTIVATED,
-                                            MESSAGE_NOTIFICATIONS_DEACTIVATED,
-                                        )
-                                        .await;
-                                    }
-                                    Err(e) => {
-                                        log::error!(
-                                            "error while changing notification status: {}",
-                                            e
-                                        );
-                                        send_message(
-                                            api,
-                                            SendMessage::new(
-                                                chat_id,
-                                                MESSAGE_ERROR_CHANGE_NOTIFICATION,
-                                            ),
-                                        )
-                                        .await;
-                                    }
-                                };
+    let title = if same_day {
+        text.message_trash_today
+    } else {
+        text.message_trash_tomorrow
+    };
+    let mut lines = vec![String::from(title.trim_end())];
+    lines.extend(dates.iter().map(|date| date.details(text)));
 
-                                Next(MainMenu)
-                            }
-                            Err(e) => {
-                                log::error!("{}", e);
+    lines.join("\n")
+}
 
-                                send_message(
-                                    api,
-                                    SendMessage::new(chat_id, MESSAGE_CHANGE_NOTIFICATION_NEGATIVE),
-                                )
-                                .await;
+/// Reminders at or after this hour are sent the day before the pickup, earlier ones on the
+/// pickup day itself.
+const EVENING_REMINDER_FROM_HOUR: u32 = 12;
+
+/// Confirms the notification time and tells whether it's on the pickup day or the day before.
+fn notification_time_message(notification_time: NaiveTime, text: &Strings) -> String {
+    format!(
+        "{}{}{}",
+        text.message_notification_time_set,
+        notification_time.format("%H:%M"),
+        if notification_time.hour() < EVENING_REMINDER_FROM_HOUR {
+            text.message_notification_time_same_day
+        } else {
+            text.message_notification_time_day_before
+        }
+    )
+}
 
-                                Next(MainMenu)
-                            }
-                        }
-                    }
-                    MainMenuQuestion::ManualRequestTomorrow => {
-                        log::info!("Manual request for tomorrows garbage dates.");
-
-                        match context.request_performer.get_tomorrows_trash(chat_id).await {
-                            Ok(t) => {
-                                let mut trash: String = t
-                                    .into_iter()
-                                    .map(|a| a.name)
-                                    .collect::<Vec<String>>()
-                                    .join(", ");
-                                if trash.is_empty() {
-                                    trash = String::from(MESSAGE_NO_TRASH_TOMORROW);
-                                } else {
-                                    trash = String::from(MESSAGE_TRASH_TOMORROW) + trash.as_str();
-                                }
-                                send_message(api, SendMessage::new(chat_id, trash))
-                            }
-                            Err(e) => {
-                                log::error!("Could not get tomorrows trash dates for manual user request: {}", e);
-                                send_message(api, SendMessage::new(chat_id, MESSAGE_ERROR_REQUEST))
-                            }
-                        }.await;
+/// The pickup a reminder at this local time is for.
+fn pickup_date(slot: NaiveDateTime) -> NaiveDate {
+    if slot.hour() < EVENING_REMINDER_FROM_HOUR {
+        slot.date()
+    } else {
+        slot.date().succ()
+    }
+}
 
-                        Next(MainMenu)
-                    }
-                    MainMenuQuestion::Delete => {
-                        log::info!("User data deletion: main menu");
+/// Minutes the scheduler catches up on after a restart or a stall, older reminders are dropped
+/// instead of arriving hours late.
+const MAX_CATCH_UP_MINUTES: i64 = 120;
 
-                        let row = vec![
-                            vec![KeyboardButton::new(YES)],
-                            vec![KeyboardButton::new(NO)],
-                        ];
-                        let markup = ReplyKeyboardMarkup::from(row)
-                            .one_time_keyboard(true)
-                            .resize_keyboard(true);
+/// Days sent and failed reminders are kept for the statistics, counted back from the pickup.
+const NOTIFICATIONS_KEPT_DAYS: i64 = 7;
 
-                        send_message(
-                            api,
-                            SendMessage::new(chat_id, DELETION).reply_markup(markup),
-                        )
-                        .await;
+/// How long a long poll request waits for updates.
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(30);
 
-                        Next(Remove)
-                    }
-                    MainMenuQuestion::RequestData => {
-                        log::info!("User data request: main menu");
+/// Pause before requesting updates again after a failed request.
+const LONG_POLL_ERROR_PAUSE: Duration = Duration::from_secs(5);
 
-                        match context.request_performer.get_my_user_data(chat_id).await {
-                            Ok(user_data) => {
-                                send_message(
-                                    api,
-                                    SendMessage::new(
-                                        chat_id,
-                                        user_data
-                                            .iter()
-                                            .map(|(a, b)| a.to_owned() + ": " + b)
-                                            .collect::<Vec<String>>()
-                                            .join("\n")
-                                            .to_string(),
-                                    ),
-                                )
-                                .await;
-                            }
-                            Err(e) => {
-                                log::error!("failed requesting user data: {}", e);
-                                send_message(
-                                    api,
-                                    SendMessage::new(chat_id, MESSAGE_ERROR_REQUEST_USER_DATA),
-                                )
-                                .await;
-                            }
-                        };
+/// The current local time in Karlsruhe, truncated to the minute.
+fn current_slot() -> NaiveDateTime {
+    let now = chrono::Utc::now()
+        .with_timezone(&chrono_tz::Europe::Berlin)
+        .naive_local();
+    now.date().and_hms(now.hour(), now.minute(), 0)
+}
 
-                        Next(MainMenu)
-                    }
-                },
-                Err(e) => {
-                    log::error!("An error occurred while parsing states: {}", e);
-                    Exit
-                }
-            },
-            _ => Exit,
-        },
-    })
+/// Formats a date like "Di 21.10.".
+fn short_date(date: NaiveDate, text: &Strings) -> String {
+    format!(
+        "{} {}",
+        text.weekdays[date.weekday().num_days_from_monday() as usize],
+        date.format("%d.%m.")
+    )
 }
 
-fn dates_to_message<T>(something: &[T]) -> String
-where
-    T: ToString,
-{
-    if let [] = something {
-        Default::default()
-    } else {
-        something
-            .iter()
-            .map(ToString::to_string)
-            .collect::<Vec<String>>()
-            .join(", ")
+/// One line per day, e.g. "Di 21.10.: 📰 Papier (Zuhause), 🗑 Restmüll (Zuhause)". Expects
+/// the dates to be ordered.
+fn dates_by_day_to_message(dates: &[TrashDate], text: &Strings) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_day: Option<NaiveDate> = None;
+
+    for date in dates {
+        if current_day == Some(date.date) {
+            if let Some(line) = lines.last_mut() {
+                line.push_str(", ");
+                line.push_str(&date.label(text));
+            }
+        } else {
+            current_day = Some(date.date);
+            lines.push(format!(
+                "{}: {}",
+                short_date(date.date, text),
+                date.label(text)
+            ));
+        }
     }
+
+    lines.join("\n")
 }
 
 impl Bot {
-    pub async fn scheduler() {
-        use clokwerk::{AsyncScheduler, Job, TimeUnits};
+    /// Sends the reminders of everyone whose notification time is `slot` (Europe/Berlin).
+    async fn notify_due_users(storage: &dyn Storage, api: &Api, slot: NaiveDateTime) {
+        let time = slot.time();
+        let pickup_date = pickup_date(slot);
+
+        log::debug!(
+            "Checking reminders for {} (pickups on {})",
+            slot,
+            pickup_date
+        );
+
+        match storage.get_users_due(pickup_date, time).await {
+            Ok(users) => {
+                if !users.is_empty() {
+                    log::info!("Found {} user(s) to notify at {}.", users.len(), slot);
+                }
 
-        let mut scheduler = AsyncScheduler::with_tz(chrono_tz::Europe::Berlin);
+                for mut user in users {
+                    match storage.get_unsubscribed_trash_types(user.client_id).await {
+                        Ok(unsubscribed) => {
+                            user.dates.retain(|date| !unsubscribed.contains(&date.name))
+                        }
+                        Err(e) => {
+                            log::warn!("Could not get unsubscribed trash types of {}: {}", user, e)
+                        }
+                    }
+                    if user.dates.is_empty() {
+                        log::debug!("{} unsubscribed from all pickups at {}.", user, pickup_date);
+                        continue;
+                    }
 
-        scheduler.every(1.day()).at("16:00:00").run(|| async {
-            log::info!("Starting scheduled trash check...");
+                    let mut trash_types: Vec<String> =
+                        user.dates.iter().map(|date| date.name.clone()).collect();
+                    trash_types.sort();
+                    trash_types.dedup();
+
+                    // Recorded before sending, so a restart or an overlapping run doesn't send
+                    // the same reminder twice.
+                    let claimed = match storage
+                        .claim_notifications(user.client_id, pickup_date, trash_types)
+                        .await
+                    {
+                        Ok(claimed) => claimed,
+                        Err(e) => {
+                            log::warn!(
+                                "Could not record reminder of {}, not sending it: {}",
+                                user,
+                                e
+                            );
+                            record_failed_notification(storage, user.client_id, pickup_date).await;
+                            continue;
+                        }
+                    };
+                    user.dates.retain(|date| claimed.contains(&date.name));
+                    if user.dates.is_empty() {
+                        log::debug!(
+                            "{} was already reminded of the pickups at {}.",
+                            user,
+                            pickup_date
+                        );
+                        continue;
+                    }
 
-            let request_performer = RequestPerformer::from_env();
+                    let language = chat_language(storage, user.client_id, None).await;
+                    let message = reminder_message(
+                        &user.dates,
+                        pickup_date == slot.date(),
+                        language.strings(),
+                    );
+                    if message.is_empty() {
+                        log::error!(
+                            "Empty date notification string, can't send message! {}",
+                            user
+                        );
+                    } else {
+                        match try_send_message(
+                            api.clone(),
+                            SendMessage::new(user.client_id, message),
+                        )
+                        .await
+                        {
+                            Ok(_) => {}
+                            Err(reason) => {
+                                record_failed_notification(storage, user.client_id, pickup_date)
+                                    .await;
+                                if let Err(e) = storage
+                                    .release_notifications(user.client_id, pickup_date, claimed)
+                                    .await
+                                {
+                                    log::warn!(
+                                        "Could not forget unsent reminder of {}: {}",
+                                        user,
+                                        e
+                                    );
+                                }
+                                if reason == SendError::Unreachable {
+                                    disable_notifications(storage, user.client_id).await;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => log::warn!("Error while getting trash dates: {}", e),
+        };
+    }
 
-            let token = env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN not set");
-            let api: Api = Api::new(token).unwrap();
+    /// Checks once per minute which users have to be notified.
+    ///
+    /// Minutes are counted in local Europe/Berlin time. Every local minute between the last and
+    /// the current check is handled exactly once, so the skipped hour when DST starts is still
+    /// processed and the repeated hour when DST ends isn't processed twice.
+    ///
+    /// After a (re)start or a stall at most the last [`MAX_CATCH_UP_MINUTES`] are caught up on,
+    /// minutes whose pickup is already over are skipped. Sent reminders are recorded in the
+    /// storage, so reminders that went out before the restart aren't repeated.
+    pub async fn scheduler(
+        storage: Arc<dyn Storage>,
+        statistics: Arc<ReminderStatistics>,
+        health: Arc<Health>,
+    ) {
+        let token = env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN not set");
+        let api: Api = Api::new(token).unwrap();
 
-            match request_performer.get_active_users_tomorrow().await {
-                Ok(users) => {
-                    log::info!("Found at least one user with an appointment tomorrow.");
+        let catch_up = chrono::Duration::minutes(MAX_CATCH_UP_MINUTES);
+        let mut last_slot = current_slot() - catch_up;
+        log::info!("Catching up on the reminders since {}", last_slot);
 
-                    for user in users {
-                        let message: String = dates_to_message(&user.dates[..]);
-                        if message.is_empty() {
-                            log::error!(
-                                "Empty date notification string, can't send message! {}",
-                                user
-                            );
-                        } else {
-                            send_message(api.clone(), SendMessage::new(user.client_id, message))
-                                .await;
-                        }
+        loop {
+            let now = current_slot();
+            if now - last_slot > catch_up {
+                log::warn!(
+                    "Skipping the reminders after {} up to {}, they would be too late.",
+                    last_slot,
+                    now - catch_up
+                );
+                last_slot = now - catch_up;
+            }
+
+            let timer = metrics::SCHEDULER_RUN_DURATION.start_timer();
+            while last_slot < now {
+                last_slot += chrono::Duration::minutes(1);
+                if last_slot.time() == NaiveTime::from_hms(0, 0, 0) {
+                    let before = last_slot.date() - chrono::Duration::days(NOTIFICATIONS_KEPT_DAYS);
+                    match storage.prune_sent_notifications(before).await {
+                        Ok(pruned) => log::debug!("Forgot {} past sent reminder(s).", pruned),
+                        Err(e) => log::warn!("Could not forget past sent reminders: {}", e),
                     }
                 }
-                Err(e) => log::warn!("Error while getting trash dates: {}", e),
-            };
-        });
+                if pickup_date(last_slot) < now.date() {
+                    log::debug!("Skipping reminders of {}, the pickup is over.", last_slot);
+                    continue;
+                }
+                Bot::notify_due_users(storage.as_ref(), &api, last_slot).await;
+            }
+            statistics.record_run(last_slot);
+            timer.observe_duration();
+            health.scheduler_tick();
 
-        loop {
-            scheduler.run_pending().await;
             tokio::time::sleep(Duration::from_secs(10)).await;
         }
     }
 
+    /// Requests updates from Telegram and dispatches them one after another. Every request marks
+    /// the loop as alive for the liveness probe, failed requests are retried after a pause.
+    async fn long_poll(api: Api, mut dispatcher: Dispatcher<Context>, health: Arc<Health>) {
+        let mut offset: Option<i64> = None;
+
+        loop {
+            health.long_poll_tick();
+
+            let mut request = GetUpdates::default().timeout(LONG_POLL_TIMEOUT);
+            if let Some(offset) = offset {
+                request = request.offset(offset);
+            }
+
+            match api.execute(request).await {
+                Ok(updates) => {
+                    for update in updates {
+                        offset = Some(update.id + 1);
+                        dispatcher.handle(update).await;
+                    }
+                }
+                Err(e) => {
+                    log::error!("Could not get updates: {}", e);
+                    tokio::time::sleep(LONG_POLL_ERROR_PAUSE).await;
+                }
+            }
+        }
+    }
+
     pub async fn start() {
+        let storage = storage::from_env();
+        let statistics = Arc::new(ReminderStatistics::default());
+        let (lookup_request_sender, lookup_request_receiver) = mpsc::channel::<Lookup>(32);
+        let health = Arc::new(Health::new(storage.clone(), lookup_request_sender.clone()));
+
+        let registry_storage = storage.clone();
+        tokio::spawn(async move { REGISTRY.keep_refreshed(registry_storage).await });
+
         // Start notificator
         log::info!("Start daily notification service...");
-        tokio::spawn(async { Bot::scheduler().await });
+        let scheduler_storage = storage.clone();
+        let scheduler_statistics = statistics.clone();
+        let scheduler_health = health.clone();
+        tokio::spawn(async move {
+            Bot::scheduler(scheduler_storage, scheduler_statistics, scheduler_health).await
+        });
 
         let token = env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN not set");
         let api: Api = Api::new(token).expect("Failed to create API");
-        let (lookup_request_sender, lookup_request_receiver) = mpsc::channel::<Lookup>(32);
+        let bot_username = api
+            .execute(GetMe)
+            .await
+            .expect("Failed to get bot user")
+            .username
+            .expect("Bot user has no username");
+        command::register_commands(&api).await;
 
         log::info!("Starting geolocation lookup service.");
-        let mut lookup_device = LocationLookup::new(lookup_request_receiver).await;
+        let lookup_device = LocationLookup::new(
+            lookup_request_receiver,
+            geocoder::from_env(),
+            GeocodingCache::from_env(),
+        )
+        .await;
 
-        tokio::spawn(async move {
-            lookup_device.start().await;
-        });
+        tokio::spawn(lookup_device.supervise());
+
+        let session_dir = env::var("SESSION_DIR").unwrap_or_else(|_| String::from("sessions"));
+        std::fs::create_dir_all(&session_dir).expect("Failed to create session directory");
+        log::info!("Storing dialogue sessions in {}", session_dir);
 
-        let tmpdir = tempdir().expect("Failed to create temp directory");
-        let dialogue_name = "BasicDialogue"; // unique dialogue name used to store state
-        let session_manager = SessionManager::new(FilesystemBackend::new(tmpdir.path()));
+        let session_manager = SessionManager::new(FilesystemBackend::new(session_dir));
+
+        if let Some(address) = metrics::address_from_env() {
+            tokio::spawn(metrics::serve(address, health.clone()));
+        }
+
+        let admin_chat_ids = admin_chat_ids_from_env();
+        log::info!("{} admin chat(s) configured.", admin_chat_ids.len());
 
         let mut dispatcher = Dispatcher::new(Context {
             session_manager: session_manager.clone(),
             api: api.clone(),
             sender: lookup_request_sender.clone(),
-            request_performer: RequestPerformer::from_env(),
+            storage,
+            bot_username,
+            admin_chat_ids,
+            statistics,
         });
 
         let (capacity, interval) = (nonzero!(3u32), Duration::from_secs(3));
@@ -727,9 +1412,20 @@ impl Bot {
             interval,
         ));
 
-        dispatcher.add_handler(Dialogue::new(session_manager, dialogue_name, bot_dialogue));
+        dispatcher.add_handler(chat_member::handle_chat_member);
+        dispatcher.add_handler(command::handle_command);
+        dispatcher.add_handler(callback::handle_callback);
+        dispatcher.add_handler(Dialogue::new(session_manager, DIALOGUE_NAME, bot_dialogue));
 
-        log::info!("Starting message handling...");
-        LongPoll::new(api, dispatcher).run().await;
+        match webhook::WebhookConfig::from_env() {
+            Some(config) => {
+                log::info!("Starting message handling by webhook...");
+                webhook::run(config, dispatcher).await;
+            }
+            None => {
+                log::info!("Starting message handling...");
+                Bot::long_poll(api, dispatcher, health).await;
+            }
+        }
     }
 }
@@ -0,0 +1,50 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Europe::Berlin;
+
+/// State of the scheduler shown to the operators. The reminder counts are kept by the storage,
+/// so they survive restarts.
+#[derive(Default)]
+pub struct ReminderStatistics {
+    last_run: Mutex<Option<NaiveDateTime>>,
+}
+
+impl ReminderStatistics {
+    /// Remembers the local time of the last minute the scheduler checked.
+    pub fn record_run(&self, slot: NaiveDateTime) {
+        match self.last_run.lock() {
+            Ok(mut last_run) => *last_run = Some(slot),
+            Err(_) => log::error!("Reminder statistics lock poisoned."),
+        }
+    }
+
+    pub fn last_run(&self) -> Option<NaiveDateTime> {
+        self.last_run.lock().ok().and_then(|last_run| *last_run)
+    }
+}
+
+/// Start and end of a local Europe/Berlin day. Midnight exists on every day, DST changes at 2:00.
+pub fn day_bounds(day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = |day: NaiveDate| {
+        Berlin
+            .from_local_datetime(&day.and_hms(0, 0, 0))
+            .unwrap()
+            .with_timezone(&Utc)
+    };
+
+    (start(day), start(day.succ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_is_shorter_when_dst_starts() {
+        let (start, end) = day_bounds(NaiveDate::from_ymd(2026, 3, 29));
+
+        assert_eq!(start, Utc.ymd(2026, 3, 28).and_hms(23, 0, 0));
+        assert_eq!(end, Utc.ymd(2026, 3, 29).and_hms(22, 0, 0));
+    }
+}
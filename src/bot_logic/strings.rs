@@ -1,51 +1,354 @@
-pub const HOUSE_NUMBER_MESSAGE: &str =
-    "Bitte gib deine Hausnummer an (die Entsorgungstermine sind abhängig von der Hausnummer).";
+/// All user-facing texts of one language, see [`crate::bot_logic::language::Language`].
+pub struct Strings {
+    pub house_number_message: &'static str,
+    pub help_message: &'static str,
+    pub house_number_question_1: &'static str,
+    pub house_number_question_2: &'static str,
+    pub confirm_street_and_number: &'static str,
+    pub message_search_could_not_find: &'static str,
+    pub message_confirm_one_of_the_streets: &'static str,
+    pub message_service_unavailable: &'static str,
+    pub message_error_request: &'static str,
+    pub message_no_trash_tomorrow: &'static str,
+    pub message_trash_tomorrow: &'static str,
+    pub message_no_trash_next_two_weeks: &'static str,
+    pub message_trash_next_two_weeks: &'static str,
+    pub message_no_trash_next_week: &'static str,
+    pub message_trash_next_week: &'static str,
+    pub message_no_next_pickups: &'static str,
+    pub message_next_pickups: &'static str,
+    pub weekdays: [&'static str; 7],
+    pub message_save_location: &'static str,
+    pub message_confirm_address_added: &'static str,
+    pub message_error_address_added: &'static str,
+    pub message_ask_address_name: &'static str,
+    pub message_address_name_too_long: &'static str,
+    pub default_address_name: &'static str,
+    pub message_ask_remove_address: &'static str,
+    pub message_no_addresses: &'static str,
+    pub message_address_removed: &'static str,
+    pub message_address_not_found: &'static str,
+    pub message_change_notification_negative: &'static str,
+    pub message_error_change_notification: &'static str,
+    pub message_error_request_user_data: &'static str,
+    pub message_enter_house_number: &'static str,
+    pub message_enter_street_name: &'static str,
+    pub message_deleted: &'static str,
+    pub message_nothing_happens: &'static str,
+    pub message_ask_search_mode: &'static str,
+    pub message_ask_search_mode_group: &'static str,
+    pub message_ask_for_manual_entry: &'static str,
+    pub message_location_lookup_timeout: &'static str,
+    pub message_ask_what_user_wants: &'static str,
+    pub hello: &'static str,
+    pub message_notifications_activated: &'static str,
+    pub message_notifications_deactivated: &'static str,
+    pub message_notify_usage: &'static str,
+    pub message_ask_calendar_reminder: &'static str,
+    pub message_no_upcoming_trash: &'static str,
+    pub calendar_file_name: &'static str,
+    pub message_ask_notification_time: &'static str,
+    pub message_invalid_notification_time: &'static str,
+    pub message_notification_time_set: &'static str,
+    pub message_notification_time_same_day: &'static str,
+    pub message_notification_time_day_before: &'static str,
+    pub message_error_notification_time: &'static str,
+    pub message_ask_toggle_trash_type: &'static str,
+    pub message_error_trash_types: &'static str,
+    pub message_only_admins: &'static str,
+    pub deletion: &'static str,
+    pub no_delete_msg: &'static str,
+    pub yes: &'static str,
+    pub no: &'static str,
+    pub menu_no_street_correct: &'static str,
+    pub menu_enter_manually: &'static str,
+    pub menu_find_automatically: &'static str,
+    pub message_ask_language: &'static str,
+    pub message_language_set: &'static str,
+    pub message_error_language: &'static str,
+    pub menu_manual_request_tomorrow: &'static str,
+    pub menu_request_next_two_weeks: &'static str,
+    pub menu_request_next_per_type: &'static str,
+    pub menu_search: &'static str,
+    pub menu_remove_address: &'static str,
+    pub menu_select_trash_types: &'static str,
+    pub menu_notification: &'static str,
+    pub state_on: &'static str,
+    pub state_off: &'static str,
+    pub menu_back: &'static str,
+    pub menu_notification_time: &'static str,
+    pub menu_set_language: &'static str,
+    pub menu_delete: &'static str,
+    pub menu_request_data: &'static str,
+    pub menu_export_calendar: &'static str,
+    pub location_correct: &'static str,
+    pub location_number_false: &'static str,
+    pub location_all_false: &'static str,
+    pub location_other_candidates: &'static str,
+    pub message_help: &'static str,
+    pub command_next: &'static str,
+    pub command_week: &'static str,
+    pub command_address: &'static str,
+    pub command_notify: &'static str,
+    pub command_delete: &'static str,
+    pub command_mydata: &'static str,
+    pub command_help: &'static str,
+    pub admin_users: &'static str,
+    pub admin_notifications_enabled: &'static str,
+    pub admin_reminders_sent_yesterday: &'static str,
+    pub admin_reminders_failed_yesterday: &'static str,
+    pub admin_last_scheduler_run: &'static str,
+    pub admin_never: &'static str,
+    pub admin_users_per_street: &'static str,
+    pub message_trash_today: &'static str,
+    /// Stored name, translated name and description of the built-in trash types.
+    pub trash_types: [(&'static str, &'static str, &'static str); 4],
+}
 
-pub const HELP_MESSAGE: &str = "Versuche den vollständigen Namen deiner Straße anzugeben. Ansonsten stelle sicher, dass deine Straße im Abfuhrkalender von Karlsruhe aufgeführt ist.\n\nGib deine Straße ein:";
+impl Strings {
+    /// The translated name of a built-in trash type, other types keep their stored name.
+    pub fn trash_type_name<'a>(&self, name: &'a str) -> &'a str {
+        self.trash_types
+            .iter()
+            .find(|(stored, _, _)| *stored == name)
+            .map_or(name, |(_, translated, _)| translated)
+    }
 
-pub const HOUSE_NUMBER_QUESTION_1: &str = "Ist das deine Hausnummer";
-pub const HOUSE_NUMBER_QUESTION_2: &str = "Stelle sicher, dass die Nummer korrekt ist, da sonst möglicherweise keine Entsorgungstermine gefunden werden können.";
-pub const CONFIRM_STREET_AND_NUMBER: &str = "Ist das die korrekte Straße und Hausnummer?";
+    /// The translated description of a built-in trash type.
+    pub fn trash_type_description(&self, name: &str) -> Option<&'static str> {
+        self.trash_types
+            .iter()
+            .find(|(stored, _, _)| *stored == name)
+            .map(|(_, _, description)| *description)
+    }
+}
 
-pub const MESSAGE_SEARCH_COULD_NOT_FIND: &str = "Konnte deine Straße nicht in der Datenbank finden. Bitte gib den Namen deiner Straße ein um Vorschläge anzuzeigen:";
-pub const MESSAGE_CONFIRM_ONE_OF_THE_STREETS: &str = "Ist deine Straße hier aufgeführt?";
-pub const MESSAGE_ERROR_STREET_SEARCH: &str =
-    "Konnte keine Verbindung mit der Datenbank aufbauen, versuche es später nochmal.";
-pub const MESSAGE_ERROR_REQUEST: &str = "Fehler beim Abruf der Daten, versuche es später nochmal!";
-pub const MESSAGE_NO_TRASH_TOMORROW: &str = "Du musst heute keinen Abfall vor die Tür stellen!";
-pub const MESSAGE_TRASH_TOMORROW: &str = "Morgen wird dieser Abfall abgeholt: ";
-pub const MESSAGE_SAVE_LOCATION: &str =
-    "Speichere deinen Standort für die Abfrage der Entsorgungstermine.";
-pub const MESSAGE_CONFIRM_ADDRESS_ADDED: &str = "Adresse hinzugefügt!";
-pub const MESSAGE_ERROR_ADDRESS_ADDED: &str =
-    "Konnte Adresse nicht hinzufügen, versuche es später nochmal!";
-pub const MESSAGE_CHANGE_NOTIFICATION_NEGATIVE: &str = "Konnte Benachrichtigungsstatus nicht finden, hast du deine Straße und Hausnummer schon hinzugefügt?";
-pub const MESSAGE_ERROR_CHANGE_NOTIFICATION: &str =
-    "Konnte Benachrichtigungsstatus nicht ändern, versuche es später nochmal!";
-pub const MESSAGE_ERROR_REQUEST_USER_DATA: &str =
-    "Konnte keine Daten finden, hast du deine Straße schon hinzugefügt?";
-pub const MESSAGE_ENTER_HOUSE_NUMBER: &str =
-    "Bitte gib die Hausnummer an, die du verwenden willst:";
-pub const MESSAGE_ENTER_STREET_NAME: &str =
-    "Bitte gib den Namen deiner Straße ein, um Vorschläge anzuzeigen:";
-pub const MESSAGE_DELETED: &str = "Gelöscht!";
-pub const MESSAGE_NOTHING_HAPPENS: &str = "Ok, nichts passiert!";
-pub const MESSAGE_ASK_SEARCH_MODE: &str =
-    "Willst du deine Adresse selbst eingeben oder willst du sie automatisch finden lassen?";
-pub const MESSAGE_ASK_FOR_MANUAL_ENTRY: &str =
-    "Konnte deinen Standort nicht zuordnen, bitte gib deine Adresse manuell ein.";
-pub const MESSAGE_ASK_WHAT_USER_WANTS: &str = "Was möchtest du tun?";
-pub const HELLO: &str = "Hallo";
-pub const MESSAGE_NOTIFICATIONS_ACTIVATED: &str = "Benachrichtigungen aktiviert";
-pub const MESSAGE_NOTIFICATIONS_DEACTIVATED: &str = "Benachrichtigungen deaktiviert";
+pub const NOTIFICATION_TIME_PRESETS: [&str; 3] = ["07:00", "16:00", "20:00"];
 
-pub const DELETION: &str = "Willst du all deine Daten löschen?";
-pub const NO_DELETE_MSG: &str =
-    "Konnte deine Daten nicht finden, hast du deine Daten schon gelöscht?";
+pub const GERMAN: Strings = Strings {
+    house_number_message: "Bitte gib deine Hausnummer an (die Entsorgungstermine sind abhängig von der Hausnummer).",
+    help_message: "Versuche den vollständigen Namen deiner Straße anzugeben. Ansonsten stelle sicher, dass deine Straße im Abfuhrkalender von Karlsruhe aufgeführt ist.\n\nGib deine Straße ein:",
+    house_number_question_1: "Ist das deine Hausnummer",
+    house_number_question_2: "Stelle sicher, dass die Nummer korrekt ist, da sonst möglicherweise keine Entsorgungstermine gefunden werden können.",
+    confirm_street_and_number: "Ist das die korrekte Straße und Hausnummer?",
+    message_search_could_not_find: "Konnte deine Straße nicht in der Datenbank finden. Bitte gib den Namen deiner Straße ein um Vorschläge anzuzeigen:",
+    message_confirm_one_of_the_streets: "Ist deine Straße hier aufgeführt?",
+    message_service_unavailable: "Die Datenbank ist gerade nicht erreichbar, versuche es in ein paar Minuten nochmal.",
+    message_error_request: "Fehler beim Abruf der Daten, versuche es später nochmal!",
+    message_no_trash_tomorrow: "Du musst heute keinen Abfall vor die Tür stellen!",
+    message_trash_tomorrow: "Morgen wird dieser Abfall abgeholt: ",
+    message_no_trash_next_two_weeks: "In den nächsten 14 Tagen wird kein Abfall abgeholt!",
+    message_trash_next_two_weeks: "Abholtermine der nächsten 14 Tage:\n",
+    message_no_trash_next_week: "In den nächsten 7 Tagen wird kein Abfall abgeholt!",
+    message_trash_next_week: "Abholtermine der nächsten 7 Tage:\n",
+    message_no_next_pickups: "Konnte keine anstehenden Abholtermine finden!",
+    message_next_pickups: "Nächste Abholung: ",
+    weekdays: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    message_save_location: "Speichere deinen Standort für die Abfrage der Entsorgungstermine.",
+    message_confirm_address_added: "Adresse hinzugefügt!",
+    message_error_address_added: "Konnte Adresse nicht hinzufügen, versuche es später nochmal!",
+    message_ask_address_name: "Unter welchem Namen soll die Adresse gespeichert werden (z.B. Zuhause, Arbeit, Eltern)? Ein vorhandener Name wird überschrieben.",
+    message_address_name_too_long: "Der Name ist zu lang, bitte wähle einen kürzeren Namen:",
+    default_address_name: "Zuhause",
+    message_ask_remove_address: "Welche Adresse willst du entfernen?",
+    message_no_addresses: "Du hast noch keine Adresse gespeichert.",
+    message_address_removed: "Adresse entfernt!",
+    message_address_not_found: "Konnte keine Adresse mit diesem Namen finden.",
+    message_change_notification_negative: "Konnte Benachrichtigungsstatus nicht finden, hast du deine Straße und Hausnummer schon hinzugefügt? Mit /address kannst du eine Adresse speichern.",
+    message_error_change_notification: "Konnte Benachrichtigungsstatus nicht ändern, versuche es später nochmal!",
+    message_error_request_user_data: "Konnte keine Daten finden, hast du deine Straße schon hinzugefügt?",
+    message_enter_house_number: "Bitte gib die Hausnummer an, die du verwenden willst:",
+    message_enter_street_name: "Bitte gib den Namen deiner Straße ein, um Vorschläge anzuzeigen:",
+    message_deleted: "Gelöscht!",
+    message_nothing_happens: "Ok, nichts passiert!",
+    message_ask_search_mode: "Willst du deine Adresse selbst eingeben oder willst du sie automatisch finden lassen?",
+    message_ask_search_mode_group: "Bitte gib deine Adresse selbst ein, automatisch finden lässt sie sich nur im privaten Chat mit dem Bot.",
+    message_ask_for_manual_entry: "Konnte deinen Standort nicht zuordnen, bitte gib deine Adresse manuell ein.",
+    message_location_lookup_timeout: "Die Standortsuche hat zu lange gedauert, bitte gib deine Adresse manuell ein.",
+    message_ask_what_user_wants: "Was möchtest du tun?",
+    hello: "Hallo",
+    message_notifications_activated: "Benachrichtigungen aktiviert",
+    message_notifications_deactivated: "Benachrichtigungen deaktiviert",
+    message_notify_usage: "Schalte die Benachrichtigungen mit /notify on oder /notify off ein oder aus.",
+    message_ask_calendar_reminder: "Sollen die Termine im Kalender eine Erinnerung um 16 Uhr am Vortag bekommen?",
+    message_no_upcoming_trash: "Konnte keine anstehenden Entsorgungstermine finden, hast du deine Straße schon hinzugefügt?",
+    calendar_file_name: "Entsorgungstermine.ics",
+    message_ask_notification_time: "Wann möchtest du benachrichtigt werden? Wähle eine Uhrzeit aus oder gib sie im Format HH:MM ein. Uhrzeiten vor 12 Uhr gelten für den Abholtag selbst, spätere für den Vortag.",
+    message_invalid_notification_time: "Das ist keine gültige Uhrzeit, bitte gib sie im Format HH:MM ein (z.B. 07:00):",
+    message_notification_time_set: "Du wirst ab jetzt um ",
+    message_notification_time_same_day: " Uhr am Abholtag benachrichtigt.",
+    message_notification_time_day_before: " Uhr am Vortag benachrichtigt.",
+    message_error_notification_time: "Konnte Benachrichtigungszeit nicht ändern, hast du deine Straße und Hausnummer schon hinzugefügt?",
+    message_ask_toggle_trash_type: "Wähle eine Abfallart aus, um sie ein- oder auszublenden.",
+    message_error_trash_types: "Konnte Abfallarten nicht ändern, hast du deine Straße schon hinzugefügt?",
+    message_only_admins: "In Gruppen können nur Administratoren die Adresse und Einstellungen ändern.",
+    deletion: "Willst du all deine Daten löschen?",
+    no_delete_msg: "Konnte deine Daten nicht finden, hast du deine Daten schon gelöscht?",
+    yes: "Ja",
+    no: "Nein",
+    menu_no_street_correct: "Keine der Straße ist richtig",
+    menu_enter_manually: "Selbst eingeben",
+    menu_find_automatically: "Automatisch finden",
+    message_ask_language: "Welche Sprache möchtest du verwenden?",
+    message_language_set: "Sprache geändert!",
+    message_error_language: "Konnte die Sprache nicht ändern, versuche es später nochmal!",
+    menu_manual_request_tomorrow: "Manuelle Abfrage",
+    menu_request_next_two_weeks: "Nächste 14 Tage",
+    menu_request_next_per_type: "Nächste Abholung je Abfallart",
+    menu_search: "Adresse hinzufügen/ändern",
+    menu_remove_address: "Adresse entfernen",
+    menu_select_trash_types: "Abfallarten auswählen",
+    menu_notification: "Benachrichtigungen",
+    state_on: "an",
+    state_off: "aus",
+    menu_back: "« Menü",
+    menu_notification_time: "Benachrichtigungszeit ändern",
+    menu_set_language: "Sprache ändern",
+    menu_delete: "Alle Daten löschen",
+    menu_request_data: "Gespeicherte Daten abfragen",
+    menu_export_calendar: "Termine als Kalender exportieren",
+    location_correct: "Ja, beides stimmt!",
+    location_number_false: "Nein, die Hausnummer stimmt nicht!",
+    location_all_false: "Nein, beides ist falsch!",
+    location_other_candidates: "Falls nicht, tippe auf eine der Adressen in der Nähe.",
+    message_help: "Schreib mir eine beliebige Nachricht, um das Menü zu öffnen, oder verwende einen dieser Befehle:",
+    command_next: "Nächster Abholtermin je Abfallart",
+    command_week: "Abholtermine der nächsten 7 Tage",
+    command_address: "Adresse hinzufügen oder ändern",
+    command_notify: "Benachrichtigungen ein- oder ausschalten (on/off)",
+    command_delete: "Alle Daten löschen",
+    command_mydata: "Gespeicherte Daten anzeigen",
+    command_help: "Alle Befehle anzeigen",
+    admin_users: "Registrierte Chats",
+    admin_notifications_enabled: "Benachrichtigungen aktiv",
+    admin_reminders_sent_yesterday: "Gestern verschickte Erinnerungen",
+    admin_reminders_failed_yesterday: "Gestern fehlgeschlagene Erinnerungen",
+    admin_last_scheduler_run: "Letzter Lauf des Schedulers",
+    admin_never: "noch nie",
+    admin_users_per_street: "Chats pro Straße:\n",
+    message_trash_today: "Heute wird dieser Abfall abgeholt: ",
+    trash_types: [
+        ("Bioabfall", "Bioabfall", "Küchen- und Gartenabfälle"),
+        ("Papier", "Papier", "Papier, Pappe und Kartonagen"),
+        ("Restmüll", "Restmüll", "Alles, was nicht verwertet werden kann"),
+        (
+            "Wertstoff",
+            "Wertstoff",
+            "Verpackungen und Gegenstände aus Kunststoff und Metall",
+        ),
+    ],
+};
 
-pub const YES: &str = "Ja";
-pub const NO: &str = "Nein";
-
-pub const MENU_NO_STREET_CORRECT: &str = "Keine der Straße ist richtig";
-pub const MENU_ENTER_MANUALLY: &str = "Selbst eingeben";
-pub const MENU_FIND_AUTOMATICALLY: &str = "Automatisch finden";
+pub const ENGLISH: Strings = Strings {
+    house_number_message: "Please enter your house number (the collection dates depend on the house number).",
+    help_message: "Try to enter the full name of your street. Otherwise make sure that your street is listed in the waste calendar of Karlsruhe.\n\nEnter your street:",
+    house_number_question_1: "Is this your house number",
+    house_number_question_2: "Make sure the number is correct, otherwise no collection dates might be found.",
+    confirm_street_and_number: "Is this the correct street and house number?",
+    message_search_could_not_find: "Could not find your street in the database. Please enter the name of your street to show suggestions:",
+    message_confirm_one_of_the_streets: "Is your street listed here?",
+    message_service_unavailable: "The database is not available right now, please try again in a few minutes.",
+    message_error_request: "Error while requesting the data, please try again later!",
+    message_no_trash_tomorrow: "You don't have to put out any waste today!",
+    message_trash_tomorrow: "This waste will be collected tomorrow: ",
+    message_no_trash_next_two_weeks: "No waste will be collected in the next 14 days!",
+    message_trash_next_two_weeks: "Collection dates of the next 14 days:\n",
+    message_no_trash_next_week: "No waste will be collected in the next 7 days!",
+    message_trash_next_week: "Collection dates of the next 7 days:\n",
+    message_no_next_pickups: "Could not find any upcoming collection dates!",
+    message_next_pickups: "Next collection: ",
+    weekdays: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    message_save_location: "Saving your location to look up the collection dates.",
+    message_confirm_address_added: "Address added!",
+    message_error_address_added: "Could not add the address, please try again later!",
+    message_ask_address_name: "Under which name should the address be saved (e.g. Home, Work, Parents)? An existing name will be overwritten.",
+    message_address_name_too_long: "The name is too long, please choose a shorter one:",
+    default_address_name: "Home",
+    message_ask_remove_address: "Which address do you want to remove?",
+    message_no_addresses: "You haven't saved an address yet.",
+    message_address_removed: "Address removed!",
+    message_address_not_found: "Could not find an address with this name.",
+    message_change_notification_negative: "Could not find your notification status, did you already add your street and house number? Use /address to save one.",
+    message_error_change_notification: "Could not change the notification status, please try again later!",
+    message_error_request_user_data: "Could not find any data, did you already add your street?",
+    message_enter_house_number: "Please enter the house number you want to use:",
+    message_enter_street_name: "Please enter the name of your street to show suggestions:",
+    message_deleted: "Deleted!",
+    message_nothing_happens: "Ok, nothing happened!",
+    message_ask_search_mode: "Do you want to enter your address yourself or should it be found automatically?",
+    message_ask_search_mode_group: "Please enter your address yourself, it can only be found automatically in a private chat with the bot.",
+    message_ask_for_manual_entry: "Could not match your location, please enter your address manually.",
+    message_location_lookup_timeout: "Looking up your location took too long, please enter your address manually.",
+    message_ask_what_user_wants: "What would you like to do?",
+    hello: "Hello",
+    message_notifications_activated: "Notifications enabled",
+    message_notifications_deactivated: "Notifications disabled",
+    message_notify_usage: "Turn notifications on or off with /notify on or /notify off.",
+    message_ask_calendar_reminder: "Should the calendar events get a reminder at 4 pm the day before?",
+    message_no_upcoming_trash: "Could not find any upcoming collection dates, did you already add your street?",
+    calendar_file_name: "Waste collection dates.ics",
+    message_ask_notification_time: "When do you want to be notified? Choose a time or enter it in the format HH:MM. Times before 12:00 apply to the collection day itself, later ones to the day before.",
+    message_invalid_notification_time: "This is not a valid time, please enter it in the format HH:MM (e.g. 07:00):",
+    message_notification_time_set: "From now on you will be notified at ",
+    message_notification_time_same_day: " on the collection day.",
+    message_notification_time_day_before: " the day before the collection.",
+    message_error_notification_time: "Could not change the notification time, did you already add your street and house number?",
+    message_ask_toggle_trash_type: "Choose a waste type to show or hide it.",
+    message_error_trash_types: "Could not change the waste types, did you already add your street?",
+    message_only_admins: "In groups only administrators can change the address and settings.",
+    deletion: "Do you want to delete all your data?",
+    no_delete_msg: "Could not find your data, did you already delete it?",
+    yes: "Yes",
+    no: "No",
+    menu_no_street_correct: "None of the streets is correct",
+    menu_enter_manually: "Enter manually",
+    menu_find_automatically: "Find automatically",
+    message_ask_language: "Which language do you want to use?",
+    message_language_set: "Language changed!",
+    message_error_language: "Could not change the language, please try again later!",
+    menu_manual_request_tomorrow: "Manual request",
+    menu_request_next_two_weeks: "Next 14 days",
+    menu_request_next_per_type: "Next collection per waste type",
+    menu_search: "Add/change address",
+    menu_remove_address: "Remove address",
+    menu_select_trash_types: "Choose waste types",
+    menu_notification: "Notifications",
+    state_on: "on",
+    state_off: "off",
+    menu_back: "« Menu",
+    menu_notification_time: "Change notification time",
+    menu_set_language: "Change language",
+    menu_delete: "Delete all data",
+    menu_request_data: "Request stored data",
+    menu_export_calendar: "Export dates as calendar",
+    location_correct: "Yes, both are correct!",
+    location_number_false: "No, the house number is wrong!",
+    location_all_false: "No, both are wrong!",
+    location_other_candidates: "If not, tap one of the nearby addresses.",
+    message_help: "Send me any message to open the menu, or use one of these commands:",
+    command_next: "Next collection date per waste type",
+    command_week: "Collection dates of the next 7 days",
+    command_address: "Add or change your address",
+    command_notify: "Turn notifications on or off (on/off)",
+    command_delete: "Delete all data",
+    command_mydata: "Show the stored data",
+    command_help: "Show all commands",
+    admin_users: "Registered chats",
+    admin_notifications_enabled: "Notifications enabled",
+    admin_reminders_sent_yesterday: "Reminders sent yesterday",
+    admin_reminders_failed_yesterday: "Reminders failed yesterday",
+    admin_last_scheduler_run: "Last scheduler run",
+    admin_never: "never",
+    admin_users_per_street: "Chats per street:\n",
+    message_trash_today: "This waste will be collected today: ",
+    trash_types: [
+        ("Bioabfall", "Organic waste", "Kitchen and garden waste"),
+        ("Papier", "Paper", "Paper, cardboard and cartons"),
+        ("Restmüll", "Residual waste", "Everything that can't be recycled"),
+        (
+            "Wertstoff",
+            "Recyclables",
+            "Packaging and items made of plastic and metal",
+        ),
+    ],
+};
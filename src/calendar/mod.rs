@@ -0,0 +1,193 @@
+use chrono::{Duration, Utc};
+
+use crate::bot_logic::strings::Strings;
+use crate::trash_dates::TrashDate;
+
+const PRODUCT_ID: &str = "-//karlsruhe_trash_bot//Entsorgungskalender//DE";
+const UID_DOMAIN: &str = "karlsruhe-trash-bot";
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Escapes a TEXT value as described in RFC 5545, section 3.3.11.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line after 75 octets (RFC 5545, section 3.1) and terminates it with CRLF.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut line_length = 0;
+
+    for c in line.chars() {
+        if line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
+
+/// UID of a pickup, derived only from the chat, address, date and trash type. Importing the
+/// calendar again updates the existing events instead of creating duplicates.
+fn event_uid(telegram_chat_id: i64, date: &TrashDate) -> String {
+    // Built from the stored names, not the translated label, the language doesn't change it.
+    let trash_type: String = format!("{}{}", date.name, date.address.as_deref().unwrap_or(""))
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+
+    format!(
+        "{}-{}-{}@{}",
+        date.date.format("%Y%m%d"),
+        trash_type,
+        telegram_chat_id,
+        UID_DOMAIN
+    )
+}
+
+/// Builds an iCalendar file with one all-day event per pickup.
+///
+/// If `reminder` is set, every event gets a display alarm that long before the start of the
+/// pickup day, e.g. 8 hours for a reminder at 16:00 the day before.
+pub fn to_ics(
+    telegram_chat_id: i64,
+    dates: &[TrashDate],
+    reminder: Option<Duration>,
+    text: &Strings,
+) -> String {
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines: Vec<String> = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:{}", PRODUCT_ID),
+        String::from("CALSCALE:GREGORIAN"),
+        String::from("METHOD:PUBLISH"),
+    ];
+
+    for date in dates {
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!("UID:{}", event_uid(telegram_chat_id, date)));
+        lines.push(format!("DTSTAMP:{}", timestamp));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", date.date.format("%Y%m%d")));
+        lines.push(format!(
+            "DTEND;VALUE=DATE:{}",
+            date.date.succ().format("%Y%m%d")
+        ));
+        lines.push(format!("SUMMARY:{}", escape_text(&date.label(text))));
+        lines.push(format!("DESCRIPTION:{}", escape_text(&date.details(text))));
+        lines.push(String::from("TRANSP:TRANSPARENT"));
+
+        if let Some(reminder) = reminder {
+            lines.push(String::from("BEGIN:VALARM"));
+            lines.push(String::from("ACTION:DISPLAY"));
+            lines.push(format!("DESCRIPTION:{}", escape_text(&date.details(text))));
+            lines.push(format!("TRIGGER:-PT{}M", reminder.num_minutes()));
+            lines.push(String::from("END:VALARM"));
+        }
+
+        lines.push(String::from("END:VEVENT"));
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::bot_logic::strings::ENGLISH;
+    use crate::trash_dates::TrashType;
+
+    fn trash_date(name: &str, address: Option<&str>) -> TrashDate {
+        TrashDate {
+            date: NaiveDate::from_ymd(2021, 3, 15),
+            trash_type: TrashType::from(name),
+            name: String::from(name),
+            address: address.map(String::from),
+        }
+    }
+
+    #[test]
+    fn escapes_text() {
+        assert_eq!(
+            escape_text("Glas, Dosen; C:\\Tonne\nHof"),
+            "Glas\\, Dosen\\; C:\\\\Tonne\\nHof"
+        );
+    }
+
+    #[test]
+    fn folds_long_lines_between_characters() {
+        assert_eq!(fold_line("BEGIN:VEVENT"), "BEGIN:VEVENT\r\n");
+
+        // The "ü" would end at the 76th octet, the whole character moves to the next line.
+        let line = format!("SUMMARY:{}Restmüll", "x".repeat(62));
+        let folded = fold_line(&line);
+
+        assert_eq!(
+            folded,
+            format!("SUMMARY:{}Rest\r\n müll\r\n", "x".repeat(62))
+        );
+        assert!(folded
+            .split("\r\n")
+            .all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{}\r\n", line));
+    }
+
+    #[test]
+    fn keeps_uid_across_exports() {
+        let dates = vec![trash_date("Glas", Some("Zuhause"))];
+        let uids = |ics: String| -> Vec<String> {
+            ics.lines()
+                .filter(|line| line.starts_with("UID:"))
+                .map(String::from)
+                .collect()
+        };
+
+        let first = uids(to_ics(42, &dates, None, &ENGLISH));
+        let second = uids(to_ics(42, &dates, None, &ENGLISH));
+
+        assert_eq!(
+            first,
+            vec!["UID:20210315-glaszuhause-42@karlsruhe-trash-bot"]
+        );
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn adds_alarm_only_with_reminder() {
+        let dates = vec![trash_date("Glas", None)];
+
+        let without_reminder = to_ics(42, &dates, None, &ENGLISH);
+        let with_reminder = to_ics(42, &dates, Some(Duration::hours(8)), &ENGLISH);
+
+        assert!(!without_reminder.contains("BEGIN:VALARM"));
+        assert!(with_reminder.contains(
+            "BEGIN:VALARM\r\nACTION:DISPLAY\r\nDESCRIPTION:🚮 Glas\r\nTRIGGER:-PT480M\r\nEND:VALARM\r\n"
+        ));
+    }
+
+    #[test]
+    fn uses_crlf_line_endings() {
+        let ics = to_ics(
+            42,
+            &[trash_date("Glas", Some("Eltern, Hinterhaus"))],
+            Some(Duration::hours(8)),
+            &ENGLISH,
+        );
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches('\n').count(), ics.matches("\r\n").count());
+        assert!(ics.contains("SUMMARY:🚮 Glas (Eltern\\, Hinterhaus)\r\n"));
+    }
+}
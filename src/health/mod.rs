@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use chrono::Utc;
+use tokio::sync::mpsc;
+
+use crate::location_lookup::Lookup;
+use crate::storage::Storage;
+
+/// The scheduler checks every few seconds, sending the reminders of a busy minute takes longer.
+const SCHEDULER_TIMEOUT_SECONDS: i64 = 5 * 60;
+
+/// A long poll request returns after its timeout at the latest, or fails and is retried.
+const LONG_POLL_TIMEOUT_SECONDS: i64 = 5 * 60;
+
+/// Never marked, e.g. the long poll loop in webhook mode.
+const NEVER: i64 = 0;
+
+fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+/// Heartbeats of the background loops and the checks behind the liveness and readiness probes.
+pub struct Health {
+    storage: Arc<dyn Storage>,
+    lookup_sender: mpsc::Sender<Lookup>,
+    scheduler_tick: AtomicI64,
+    long_poll_tick: AtomicI64,
+}
+
+impl Health {
+    pub fn new(storage: Arc<dyn Storage>, lookup_sender: mpsc::Sender<Lookup>) -> Self {
+        Health {
+            storage,
+            lookup_sender,
+            scheduler_tick: AtomicI64::new(now()),
+            long_poll_tick: AtomicI64::new(NEVER),
+        }
+    }
+
+    pub fn scheduler_tick(&self) {
+        self.scheduler_tick.store(now(), Ordering::Relaxed);
+    }
+
+    pub fn long_poll_tick(&self) {
+        self.long_poll_tick.store(now(), Ordering::Relaxed);
+    }
+
+    /// Fails if the long poll loop stopped requesting updates.
+    pub fn live(&self) -> Result<(), String> {
+        let tick = self.long_poll_tick.load(Ordering::Relaxed);
+
+        if tick != NEVER && now() - tick > LONG_POLL_TIMEOUT_SECONDS {
+            return Err(format!("long poll loop stalled for {}s", now() - tick));
+        }
+        Ok(())
+    }
+
+    /// Fails if the storage doesn't answer, the location lookup task is gone or the scheduler
+    /// didn't tick recently.
+    pub async fn ready(&self) -> Result<(), String> {
+        self.live()?;
+
+        if self.lookup_sender.is_closed() {
+            return Err(String::from("location lookup task stopped"));
+        }
+
+        let tick = self.scheduler_tick.load(Ordering::Relaxed);
+        if now() - tick > SCHEDULER_TIMEOUT_SECONDS {
+            return Err(format!("scheduler didn't tick for {}s", now() - tick));
+        }
+
+        self.storage
+            .get_trash_types()
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("storage not available: {}", e))
+    }
+}
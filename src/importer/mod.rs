@@ -0,0 +1,390 @@
+use std::env;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::NaiveDate;
+use reqwest::Client;
+
+use crate::storage::Storage;
+use crate::trash_dates::{TrashDate, TrashType};
+use crate::trash_types::REGISTRY;
+
+static DEFAULT_CALENDAR_ENDPOINT: &str = "https://web6.karlsruhe.de/service/abfall/akal/akal.php";
+
+/// Fetches the Karlsruhe waste calendar ("Abfuhrkalender") and stores its dates.
+pub struct Importer {
+    endpoint: String,
+    client: Client,
+    storage: Arc<dyn Storage>,
+}
+
+fn trash_date(date: NaiveDate, name: &str) -> TrashDate {
+    TrashDate {
+        date,
+        trash_type: TrashType::from(name),
+        name: String::from(name),
+        address: None,
+    }
+}
+
+/// Finds the known trash type a calendar label belongs to, e.g. "Restmüll, 14-täglich". The
+/// calendar uses the names stored in the `trash_types` table.
+fn match_trash_type(label: &str) -> Option<String> {
+    let label = label.trim().to_lowercase();
+    REGISTRY
+        .names()
+        .into_iter()
+        .find(|name| label.starts_with(&name.to_lowercase()))
+}
+
+/// Extracts every `dd.mm.yyyy` date in `text`.
+fn find_dates(text: &str) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+
+    let mut i = 0;
+    while i + 10 <= text.len() {
+        match text
+            .get(i..i + 10)
+            .and_then(|candidate| NaiveDate::parse_from_str(candidate, "%d.%m.%Y").ok())
+        {
+            Some(date) => {
+                dates.push(date);
+                i += 10;
+            }
+            None => i += 1,
+        }
+    }
+
+    dates
+}
+
+/// Removes all html tags from `html`, tags are replaced by a single space.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+}
+
+/// Parses the html page of the waste calendar.
+///
+/// Each trash type is listed in its own `row` element, the type name in the `col_3-2` column and
+/// the pickup dates in the `col_3-3` column:
+///
+/// ```html
+/// <div class="row">
+///     <div class="col_3-2">Restmüll, 14-täglich</div>
+///     <div class="col_3-3">am 21.10.2026<br />am 04.11.2026</div>
+/// </div>
+/// ```
+///
+/// Rows for trash types that aren't in the `trash_types` table (e.g. Sperrmüll) are skipped.
+pub fn parse_calendar_html(html: &str) -> Result<Vec<TrashDate>> {
+    let mut dates = Vec::new();
+
+    for row in html.split("class=\"row\"").skip(1) {
+        let label = match row.split("class=\"col_3-2\"").nth(1) {
+            Some(column) => strip_tags(column.split("class=\"col_3-3\"").next().unwrap_or("")),
+            None => continue,
+        };
+        let name = match match_trash_type(&label) {
+            Some(name) => name,
+            None => {
+                log::info!("Skipping unknown trash type in calendar: {}", label.trim());
+                continue;
+            }
+        };
+
+        if let Some(column) = row.split("class=\"col_3-3\"").nth(1) {
+            dates.extend(
+                find_dates(&strip_tags(column))
+                    .into_iter()
+                    .map(|date| trash_date(date, &name)),
+            );
+        }
+    }
+
+    if dates.is_empty() {
+        Err(Error::msg("no dates found in waste calendar page"))
+    } else {
+        Ok(dates)
+    }
+}
+
+/// Parses the csv export of the waste calendar, one `date;trash type` pair per line.
+///
+/// The header line and lines for unknown trash types are skipped, `,` is accepted as separator
+/// as well.
+pub fn parse_calendar_csv(csv: &str) -> Result<Vec<TrashDate>> {
+    let mut dates = Vec::new();
+
+    for (line_number, line) in csv.lines().enumerate() {
+        let mut columns = line.split(|c| c == ';' || c == ',');
+        let (date, label) = match (columns.next(), columns.next()) {
+            (Some(date), Some(label)) => (date.trim().trim_matches('"'), label.trim_matches('"')),
+            _ => continue,
+        };
+
+        let date = match NaiveDate::parse_from_str(date, "%d.%m.%Y") {
+            Ok(date) => date,
+            Err(_) if line_number == 0 => continue,
+            Err(e) => {
+                return Err(Error::msg(format!(
+                    "invalid date in line {}: {}",
+                    line_number + 1,
+                    e
+                )))
+            }
+        };
+
+        match match_trash_type(label) {
+            Some(name) => dates.push(trash_date(date, &name)),
+            None => log::info!("Skipping unknown trash type in calendar: {}", label.trim()),
+        }
+    }
+
+    Ok(dates)
+}
+
+/// Parses a street list, one `karlsruhe_id;name` pair per line, into `(karlsruhe_id, name)`
+/// pairs. The ids are those the waste calendar expects as `strasse`. A header line is skipped.
+pub fn parse_streets_csv(csv: &str) -> Result<Vec<(i64, String)>> {
+    let mut streets = Vec::new();
+
+    for (line_number, line) in csv.lines().enumerate() {
+        let mut columns = line.splitn(2, ';');
+        let (id, name) = match (columns.next(), columns.next()) {
+            (Some(id), Some(name)) => (id.trim().trim_matches('"'), name.trim().trim_matches('"')),
+            _ => continue,
+        };
+
+        match id.parse() {
+            Ok(_) if name.is_empty() => {
+                return Err(Error::msg(format!(
+                    "missing street name in line {}",
+                    line_number + 1
+                )))
+            }
+            Ok(id) => streets.push((id, String::from(name))),
+            Err(_) if line_number == 0 => continue,
+            Err(e) => {
+                return Err(Error::msg(format!(
+                    "invalid street id in line {}: {}",
+                    line_number + 1,
+                    e
+                )))
+            }
+        }
+    }
+
+    Ok(streets)
+}
+
+impl Importer {
+    pub fn new(endpoint: String, storage: Arc<dyn Storage>) -> Self {
+        Importer {
+            endpoint,
+            client: Client::new(),
+            storage,
+        }
+    }
+
+    pub fn from_env(storage: Arc<dyn Storage>) -> Self {
+        Self::new(
+            env::var("KARLSRUHE_CALENDAR_ENDPOINT")
+                .unwrap_or_else(|_| String::from(DEFAULT_CALENDAR_ENDPOINT)),
+            storage,
+        )
+    }
+
+    /// Downloads and parses the calendar of a street (`streets.karlsruhe_id`) and house number.
+    pub async fn fetch(&self, karlsruhe_id: i64, house_number: &str) -> Result<Vec<TrashDate>> {
+        let response = self
+            .client
+            .get(&self.endpoint)
+            .query(&[
+                ("strasse", karlsruhe_id.to_string()),
+                ("hausnr", house_number.to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let is_csv = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map_or(false, |content_type| content_type.contains("csv"));
+        let body = response.text().await?;
+
+        if is_csv {
+            parse_calendar_csv(&body)
+        } else {
+            parse_calendar_html(&body)
+        }
+    }
+
+    /// Imports the calendar of every registered address, returns the number of new dates.
+    pub async fn import_all(&self) -> Result<usize> {
+        let mut inserted = 0;
+
+        for address in self.storage.get_registered_addresses().await? {
+            let house_number = address.house_number.clone().unwrap_or_default();
+
+            match self.fetch(address.karlsruhe_id, &house_number).await {
+                Ok(dates) => {
+                    inserted += self.storage.upsert_dates(&address, dates).await?;
+                }
+                Err(e) => log::error!(
+                    "Could not import waste calendar for address {} (street {} {}): {}",
+                    address.id,
+                    address.karlsruhe_id,
+                    house_number,
+                    e
+                ),
+            }
+        }
+
+        log::info!("Imported {} new dates.", inserted);
+        Ok(inserted)
+    }
+}
+
+/// Writes one `date<TAB>trash type` line per date, the output of a single address import.
+fn print_dates(dates: &[TrashDate], output: &mut impl Write) -> io::Result<()> {
+    for date in dates {
+        writeln!(output, "{}\t{}", date.date.format("%Y-%m-%d"), date.name)?;
+    }
+    output.flush()
+}
+
+/// Entry point of the `import` subcommand.
+///
+/// Without arguments the calendars of all registered addresses are imported. `streets <file>`
+/// stores the streets of a street list. With a street id and house number the parsed dates are
+/// only printed.
+pub async fn run(args: Vec<String>, storage: Arc<dyn Storage>) -> Result<()> {
+    REGISTRY.refresh(storage.as_ref()).await?;
+    let importer = Importer::from_env(storage);
+
+    match &args[..] {
+        [] => {
+            importer.import_all().await?;
+        }
+        [command, path] if command == "streets" => {
+            let streets = parse_streets_csv(&std::fs::read_to_string(path)?)?;
+            let stored = importer.storage.upsert_streets(streets).await?;
+            log::info!("Stored {} streets.", stored);
+        }
+        [karlsruhe_id, house_number] => {
+            let dates = importer.fetch(karlsruhe_id.parse()?, house_number).await?;
+            print_dates(&dates, &mut io::stdout().lock())?;
+        }
+        _ => {
+            return Err(Error::msg(
+                "usage: trash_bot import [streets <file> | <karlsruhe street id> <house number>]",
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32, name: &str) -> TrashDate {
+        trash_date(NaiveDate::from_ymd(year, month, day), name)
+    }
+
+    #[test]
+    fn parses_calendar_html() {
+        let dates =
+            parse_calendar_html(include_str!("../../fixtures/abfuhrkalender.html")).unwrap();
+
+        assert_eq!(
+            dates,
+            vec![
+                date(2026, 10, 21, "Restmüll"),
+                date(2026, 11, 4, "Restmüll"),
+                date(2026, 10, 20, "Bioabfall"),
+                date(2026, 10, 27, "Bioabfall"),
+                date(2026, 10, 23, "Wertstoff"),
+                date(2026, 10, 30, "Papier"),
+            ]
+        );
+        assert_eq!(dates[0].trash_type, TrashType::Miscellaneous);
+    }
+
+    #[test]
+    fn rejects_html_without_dates() {
+        assert!(parse_calendar_html("<html><body>Keine Termine</body></html>").is_err());
+    }
+
+    #[test]
+    fn parses_calendar_csv() {
+        let dates = parse_calendar_csv(include_str!("../../fixtures/abfuhrkalender.csv")).unwrap();
+
+        assert_eq!(
+            dates,
+            vec![
+                date(2026, 10, 20, "Bioabfall"),
+                date(2026, 10, 21, "Restmüll"),
+                date(2026, 10, 23, "Wertstoff"),
+                date(2026, 10, 27, "Bioabfall"),
+                date(2026, 10, 30, "Papier"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_csv_with_invalid_date() {
+        assert!(parse_calendar_csv("Datum;Abfallart\n32.10.2026;Papier").is_err());
+    }
+
+    #[test]
+    fn parses_streets_csv() {
+        let streets = parse_streets_csv(include_str!("../../fixtures/streets.csv")).unwrap();
+
+        assert_eq!(streets.len(), 4);
+        assert_eq!(streets[0], (1234, String::from("Kaiserstraße")));
+        assert_eq!(streets[3], (2201, String::from("Karl-Friedrich-Straße")));
+    }
+
+    #[test]
+    fn rejects_streets_without_name() {
+        assert!(parse_streets_csv("1234;").is_err());
+    }
+
+    #[test]
+    fn prints_one_line_per_date() {
+        let mut output = Vec::new();
+        print_dates(
+            &[
+                date(2026, 10, 20, "Bioabfall"),
+                date(2026, 10, 21, "Restmüll"),
+            ],
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2026-10-20\tBioabfall\n2026-10-21\tRestmüll\n"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::location_lookup::LocationCandidate;
+use crate::metrics;
+
+/// Four decimal places are about 11 m north-south and 7 m east-west in Karlsruhe.
+const KEY_PRECISION: usize = 4;
+
+/// How often new entries are written to the cache file. Entries of a crash in between are lost,
+/// that only costs a geocoder request for each of them.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+const DEFAULT_TTL_DAYS: i64 = 90;
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Empty if the geocoder found nothing, that answer doesn't change often either.
+    candidates: Vec<LocationCandidate>,
+    stored_at: i64,
+}
+
+/// Reverse geocoding results keyed by rounded coordinates, stored in a json file so they
+/// survive restarts.
+pub struct GeocodingCache {
+    path: Option<PathBuf>,
+    ttl_seconds: i64,
+    max_entries: usize,
+    entries: HashMap<String, CacheEntry>,
+    /// Whether there are entries that aren't written to the file yet.
+    dirty: bool,
+}
+
+impl GeocodingCache {
+    /// Creates the cache configured by `GEOCODING_CACHE_PATH`, `GEOCODING_CACHE_TTL_DAYS` and
+    /// `GEOCODING_CACHE_SIZE`. An empty path keeps the cache in memory only.
+    pub fn from_env() -> Self {
+        let path = env::var("GEOCODING_CACHE_PATH")
+            .unwrap_or_else(|_| String::from("geocoding_cache.json"));
+        let ttl_days = env::var("GEOCODING_CACHE_TTL_DAYS")
+            .map(|days| {
+                days.parse()
+                    .expect("GEOCODING_CACHE_TTL_DAYS is not a number of days")
+            })
+            .unwrap_or(DEFAULT_TTL_DAYS);
+        let max_entries = env::var("GEOCODING_CACHE_SIZE")
+            .map(|size| {
+                size.parse()
+                    .expect("GEOCODING_CACHE_SIZE is not a number of entries")
+            })
+            .unwrap_or(DEFAULT_MAX_ENTRIES);
+
+        let path = if path.is_empty() {
+            log::info!("Keeping geocoding cache in memory");
+            None
+        } else {
+            log::info!("Storing geocoding cache in {}", path);
+            Some(PathBuf::from(path))
+        };
+
+        GeocodingCache::new(path, ttl_days, max_entries)
+    }
+
+    /// Creates a cache stored at `path`, or kept in memory only without a path, and loads the
+    /// entries stored before.
+    pub fn new(path: Option<PathBuf>, ttl_days: i64, max_entries: usize) -> Self {
+        let mut cache = GeocodingCache {
+            path,
+            ttl_seconds: ttl_days * 24 * 60 * 60,
+            max_entries,
+            entries: HashMap::new(),
+            dirty: false,
+        };
+        if let Err(e) = cache.load() {
+            log::error!("Could not read geocoding cache, starting empty: {}", e);
+        }
+        cache
+    }
+
+    fn key(longitude: f32, latitude: f32) -> String {
+        format!(
+            "{:.*},{:.*}",
+            KEY_PRECISION, longitude, KEY_PRECISION, latitude
+        )
+    }
+
+    fn load(&mut self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        self.entries = serde_json::from_str(&content)?;
+
+        let oldest_allowed = now() - self.ttl_seconds;
+        self.entries
+            .retain(|_, entry| entry.stored_at >= oldest_allowed);
+        log::info!("Loaded {} cached locations", self.entries.len());
+        Ok(())
+    }
+
+    /// Writes the cache to a temporary file first, a crash never leaves a half written cache.
+    async fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => return Ok(()),
+        };
+        let content = serde_json::to_string(&self.entries)?;
+
+        tokio::task::spawn_blocking(move || {
+            let temporary = path.with_extension("tmp");
+            fs::write(&temporary, content)?;
+            fs::rename(&temporary, &path)
+        })
+        .await??;
+        Ok(())
+    }
+
+    /// The cached candidates of a location, `None` on a miss. The distances are those of the
+    /// first lookup, which was at most a few meters away.
+    pub fn get(&self, longitude: f32, latitude: f32) -> Option<Vec<LocationCandidate>> {
+        let key = GeocodingCache::key(longitude, latitude);
+
+        match self.entries.get(&key) {
+            Some(entry) if entry.stored_at >= now() - self.ttl_seconds => {
+                log::info!("Geocoding cache hit: {}", key);
+                metrics::GEOCODING_CACHE.with_label_values(&["hit"]).inc();
+                Some(entry.candidates.clone())
+            }
+            _ => {
+                log::info!("Geocoding cache miss: {}", key);
+                metrics::GEOCODING_CACHE.with_label_values(&["miss"]).inc();
+                None
+            }
+        }
+    }
+
+    /// Stores a result and drops the oldest entries beyond the size limit. The file is only
+    /// written by [`GeocodingCache::flush`].
+    pub fn insert(&mut self, longitude: f32, latitude: f32, candidates: Vec<LocationCandidate>) {
+        self.entries.insert(
+            GeocodingCache::key(longitude, latitude),
+            CacheEntry {
+                candidates,
+                stored_at: now(),
+            },
+        );
+        self.dirty = true;
+
+        if self.entries.len() > self.max_entries {
+            let mut ages: Vec<(i64, String)> = self
+                .entries
+                .iter()
+                .map(|(key, entry)| (entry.stored_at, key.clone()))
+                .collect();
+            ages.sort();
+
+            let excess = self.entries.len() - self.max_entries;
+            for (_, key) in ages.into_iter().take(excess) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// Writes the entries stored since the last flush, at most once per [`FLUSH_INTERVAL`] as
+    /// called by the lookup worker.
+    pub async fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        match self.save().await {
+            Ok(()) => self.dirty = false,
+            Err(e) => log::error!("Could not write geocoding cache: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location_lookup::LocationResult;
+
+    fn candidate() -> LocationCandidate {
+        LocationCandidate {
+            location: LocationResult {
+                street: String::from("Marktplatz"),
+                house_number: Some(String::from("1")),
+                city: String::from("Karlsruhe"),
+                country: String::from("Deutschland"),
+            },
+            distance: 12.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_entries_on_flush() {
+        let path = env::temp_dir().join(format!("geocoding_cache_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut cache = GeocodingCache::new(Some(path.clone()), 90, 10);
+        cache.insert(8.4037, 49.0094, vec![candidate()]);
+        assert!(!path.exists());
+
+        cache.flush().await;
+        let reloaded = GeocodingCache::new(Some(path.clone()), 90, 10);
+        fs::remove_file(&path).unwrap();
+
+        let candidates = reloaded.get(8.40372, 49.00938).unwrap();
+        assert_eq!(candidates[0].location, candidate().location);
+    }
+
+    #[test]
+    fn drops_oldest_entries_beyond_size() {
+        let mut cache = GeocodingCache::new(None, 90, 2);
+        for (i, longitude) in [8.401, 8.402, 8.403].iter().enumerate() {
+            cache.insert(*longitude, 49.0094, vec![candidate()]);
+            cache
+                .entries
+                .values_mut()
+                .for_each(|entry| entry.stored_at -= 10 * (i as i64 + 1));
+        }
+
+        assert!(cache.get(8.401, 49.0094).is_none());
+        assert!(cache.get(8.403, 49.0094).is_some());
+    }
+}
@@ -0,0 +1,486 @@
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::location_lookup::offline::OfflineGeocoder;
+use crate::location_lookup::{distance_meters, LocationCandidate, LocationResult};
+
+static DEFAULT_NOMINATIM_ENDPOINT: &str = "https://nominatim.openstreetmap.org/";
+static DEFAULT_PHOTON_ENDPOINT: &str = "https://photon.komoot.io/";
+
+/// Nominatim and Photon ask for an identifying user agent.
+static USER_AGENT: &str = concat!("karlsruhe_trash_bot/", env!("CARGO_PKG_VERSION"));
+
+/// Public instances allow about one request per second.
+const PUBLIC_INSTANCE_THROTTLE: Duration = Duration::from_secs(1);
+
+/// Fixtures further away than this (in degrees, about 100 m) don't match a location.
+const FIXTURE_TOLERANCE: f32 = 0.001;
+
+/// Finds the addresses near a location.
+#[async_trait]
+pub trait Geocoder: Send + Sync {
+    /// Up to `limit` addresses near a location, nearest first. Empty if there is no address
+    /// nearby.
+    async fn reverse(
+        &self,
+        longitude: f32,
+        latitude: f32,
+        limit: usize,
+    ) -> Result<Vec<LocationCandidate>>;
+
+    /// Pause between two requests, so the usage policy of the service is kept.
+    fn throttle(&self) -> Duration {
+        PUBLIC_INSTANCE_THROTTLE
+    }
+
+    /// Whether the answers come from local data. Caching them saves nothing and would keep old
+    /// answers after the data changed.
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Creates the geocoder selected by `GEOCODER` (`nominatim`, `photon`, `offline` or `mock`).
+pub fn from_env() -> Arc<dyn Geocoder> {
+    match env::var("GEOCODER")
+        .unwrap_or_else(|_| String::from("nominatim"))
+        .as_str()
+    {
+        "nominatim" => {
+            let endpoint = env::var("OPENSTREETMAP_ENDPOINT")
+                .unwrap_or_else(|_| String::from(DEFAULT_NOMINATIM_ENDPOINT));
+            log::info!("Using nominatim geocoder at {}", endpoint);
+            let extract = env::var("ADDRESS_EXTRACT").ok().map(|path| {
+                log::info!("Adding nearby addresses from {}", path);
+                OfflineGeocoder::from_file(&path).expect("Failed to read address extract")
+            });
+            Arc::new(Nominatim::new(endpoint, extract))
+        }
+        "photon" => {
+            let endpoint = env::var("PHOTON_ENDPOINT")
+                .unwrap_or_else(|_| String::from(DEFAULT_PHOTON_ENDPOINT));
+            log::info!("Using photon geocoder at {}", endpoint);
+            Arc::new(Photon::new(endpoint))
+        }
+        "offline" => {
+            let path = env::var("ADDRESS_EXTRACT")
+                .expect("Address extract missing, set env variable 'ADDRESS_EXTRACT'.");
+            log::info!("Using offline geocoder with addresses from {}", path);
+            Arc::new(OfflineGeocoder::from_file(&path).expect("Failed to read address extract"))
+        }
+        "mock" => {
+            let path = env::var("GEOCODER_FIXTURES")
+                .expect("Geocoder fixtures missing, set env variable 'GEOCODER_FIXTURES'.");
+            log::info!("Using mock geocoder with fixtures from {}", path);
+            Arc::new(MockGeocoder::from_file(&path).expect("Failed to read geocoder fixtures"))
+        }
+        other => panic!(
+            "Unknown geocoder '{}', set env variable 'GEOCODER' to 'nominatim', 'photon', 'offline' or 'mock'.",
+            other
+        ),
+    }
+}
+
+fn reverse_url(endpoint: &str) -> String {
+    format!("{}/reverse", endpoint.trim_end_matches('/'))
+}
+
+fn client() -> Client {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .expect("Failed to create http client")
+}
+
+/// Orders candidates by distance and keeps the nearest `limit` different addresses.
+fn nearest_candidates(
+    mut candidates: Vec<LocationCandidate>,
+    limit: usize,
+) -> Vec<LocationCandidate> {
+    candidates.sort_by(|a, b| {
+        a.distance
+            .partial_cmp(&b.distance)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut nearest: Vec<LocationCandidate> = Vec::new();
+    for candidate in candidates {
+        if nearest.len() < limit
+            && !nearest
+                .iter()
+                .any(|known| known.location == candidate.location)
+        {
+            nearest.push(candidate);
+        }
+    }
+    nearest
+}
+
+#[derive(Debug, Deserialize)]
+struct NominatimAddress {
+    road: Option<String>,
+    house_number: Option<String>,
+    city: Option<String>,
+    town: Option<String>,
+    village: Option<String>,
+    country: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NominatimResponse {
+    lon: Option<String>,
+    lat: Option<String>,
+    address: Option<NominatimAddress>,
+    error: Option<String>,
+}
+
+/// The address Nominatim answered with and its distance to the location, `None` if Nominatim
+/// found nothing or no road.
+fn nominatim_candidate(
+    response: NominatimResponse,
+    longitude: f32,
+    latitude: f32,
+) -> Option<LocationCandidate> {
+    // Nominatim answers "Unable to geocode" with status 200 if nothing is nearby.
+    if let Some(error) = response.error {
+        log::debug!("Nominatim found nothing: {}", error);
+        return None;
+    }
+
+    // The position of the found address, the location itself if it is missing.
+    let position = |coordinate: Option<String>, default: f32| {
+        coordinate
+            .and_then(|coordinate| coordinate.parse().ok())
+            .unwrap_or(default as f64)
+    };
+    let distance = distance_meters(
+        longitude as f64,
+        latitude as f64,
+        position(response.lon, longitude),
+        position(response.lat, latitude),
+    );
+
+    let address = response.address?;
+    Some(LocationCandidate {
+        location: LocationResult {
+            street: address.road?,
+            house_number: address.house_number,
+            city: address
+                .city
+                .or(address.town)
+                .or(address.village)
+                .unwrap_or_default(),
+            country: address.country.unwrap_or_default(),
+        },
+        distance,
+    })
+}
+
+/// Reverse geocoding with the Nominatim API of OpenStreetMap.
+///
+/// A reverse request only answers with the nearest address. With an address extract the other
+/// house numbers nearby are added from it, see [`OfflineGeocoder`].
+pub struct Nominatim {
+    endpoint: String,
+    client: Client,
+    extract: Option<OfflineGeocoder>,
+}
+
+impl Nominatim {
+    pub fn new(endpoint: String, extract: Option<OfflineGeocoder>) -> Self {
+        Nominatim {
+            endpoint,
+            client: client(),
+            extract,
+        }
+    }
+}
+
+#[async_trait]
+impl Geocoder for Nominatim {
+    async fn reverse(
+        &self,
+        longitude: f32,
+        latitude: f32,
+        limit: usize,
+    ) -> Result<Vec<LocationCandidate>> {
+        let response: NominatimResponse = self
+            .client
+            .get(&reverse_url(&self.endpoint))
+            .query(&[
+                ("lon", longitude.to_string()),
+                ("lat", latitude.to_string()),
+                ("format", String::from("jsonv2")),
+                ("addressdetails", String::from("1")),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let mut candidates: Vec<LocationCandidate> =
+            nominatim_candidate(response, longitude, latitude)
+                .into_iter()
+                .collect();
+        if let Some(extract) = &self.extract {
+            candidates.extend(extract.reverse(longitude, latitude, limit).await?);
+        }
+
+        Ok(nearest_candidates(candidates, limit))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PhotonProperties {
+    /// Kind of the feature, e.g. `house` or `street`.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    name: Option<String>,
+    street: Option<String>,
+    housenumber: Option<String>,
+    city: Option<String>,
+    country: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PhotonGeometry {
+    /// Longitude and latitude.
+    coordinates: (f64, f64),
+}
+
+#[derive(Debug, Deserialize)]
+struct PhotonFeature {
+    geometry: PhotonGeometry,
+    properties: PhotonProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct PhotonResponse {
+    features: Vec<PhotonFeature>,
+}
+
+/// The addresses of Photon's features with their distance to the location, nearest first.
+fn photon_candidates(
+    response: PhotonResponse,
+    longitude: f32,
+    latitude: f32,
+) -> Vec<LocationCandidate> {
+    let candidates: Vec<LocationCandidate> = response
+        .features
+        .into_iter()
+        .filter_map(|feature| {
+            let (feature_longitude, feature_latitude) = feature.geometry.coordinates;
+            let properties = feature.properties;
+            // Roads have their name but no street.
+            let street = match properties.kind.as_deref() {
+                Some("street") => properties.street.or(properties.name),
+                _ => properties.street,
+            };
+
+            Some(LocationCandidate {
+                location: LocationResult {
+                    street: street?,
+                    house_number: properties.housenumber,
+                    city: properties.city.unwrap_or_default(),
+                    country: properties.country.unwrap_or_default(),
+                },
+                distance: distance_meters(
+                    longitude as f64,
+                    latitude as f64,
+                    feature_longitude,
+                    feature_latitude,
+                ),
+            })
+        })
+        .collect();
+
+    nearest_candidates(candidates, usize::MAX)
+}
+
+/// Reverse geocoding with Photon, e.g. a self-hosted instance without a request limit.
+pub struct Photon {
+    endpoint: String,
+    client: Client,
+}
+
+impl Photon {
+    pub fn new(endpoint: String) -> Self {
+        Photon {
+            endpoint,
+            client: client(),
+        }
+    }
+}
+
+#[async_trait]
+impl Geocoder for Photon {
+    async fn reverse(
+        &self,
+        longitude: f32,
+        latitude: f32,
+        limit: usize,
+    ) -> Result<Vec<LocationCandidate>> {
+        let response: PhotonResponse = self
+            .client
+            .get(&reverse_url(&self.endpoint))
+            .query(&[
+                ("lon", longitude.to_string()),
+                ("lat", latitude.to_string()),
+                ("limit", limit.to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(photon_candidates(response, longitude, latitude))
+    }
+}
+
+/// A location and the address the mock answers for it.
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    longitude: f32,
+    latitude: f32,
+    #[serde(flatten)]
+    result: LocationResult,
+}
+
+/// Answers from a json file instead of a service, for testing without network access. A
+/// location gets the addresses of the fixtures within about 100 m.
+///
+/// See `fixtures/geocoder.json` for the format.
+pub struct MockGeocoder {
+    fixtures: Vec<Fixture>,
+}
+
+impl MockGeocoder {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let fixtures: Vec<Fixture> = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| Error::msg(format!("invalid geocoder fixtures: {}", e)))?;
+
+        Ok(MockGeocoder { fixtures })
+    }
+}
+
+#[async_trait]
+impl Geocoder for MockGeocoder {
+    async fn reverse(
+        &self,
+        longitude: f32,
+        latitude: f32,
+        limit: usize,
+    ) -> Result<Vec<LocationCandidate>> {
+        let candidates: Vec<LocationCandidate> = self
+            .fixtures
+            .iter()
+            .filter(|fixture| {
+                (fixture.longitude - longitude).abs() <= FIXTURE_TOLERANCE
+                    && (fixture.latitude - latitude).abs() <= FIXTURE_TOLERANCE
+            })
+            .map(|fixture| LocationCandidate {
+                location: fixture.result.clone(),
+                distance: distance_meters(
+                    longitude as f64,
+                    latitude as f64,
+                    fixture.longitude as f64,
+                    fixture.latitude as f64,
+                ),
+            })
+            .collect();
+
+        Ok(nearest_candidates(candidates, limit))
+    }
+
+    fn throttle(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_nominatim_response() {
+        let response: NominatimResponse =
+            serde_json::from_str(include_str!("../../fixtures/nominatim.json")).unwrap();
+
+        let candidate = nominatim_candidate(response, 8.4037, 49.0094).unwrap();
+
+        assert_eq!(candidate.location.to_string(), "Karlsruhe, Marktplatz 1");
+        assert_eq!(candidate.location.country, "Deutschland");
+        assert!(candidate.distance < 15.0);
+    }
+
+    #[test]
+    fn maps_nominatim_error_to_nothing() {
+        let response: NominatimResponse =
+            serde_json::from_str(r#"{"error": "Unable to geocode"}"#).unwrap();
+
+        assert!(nominatim_candidate(response, 8.4037, 49.0094).is_none());
+    }
+
+    #[test]
+    fn maps_photon_features() {
+        let response: PhotonResponse =
+            serde_json::from_str(include_str!("../../fixtures/photon.json")).unwrap();
+
+        let candidates = photon_candidates(response, 8.4037, 49.0094);
+
+        let addresses: Vec<String> = candidates.iter().map(|c| c.location.to_string()).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                "Karlsruhe, Marktplatz 1",
+                "Karlsruhe, Kaiserstraße",
+                "Karlsruhe, Kaiserstraße 146"
+            ]
+        );
+        assert_eq!(candidates[0].location.country, "Deutschland");
+        assert!(candidates[0].distance < 15.0);
+    }
+
+    #[test]
+    fn keeps_nearest_different_addresses() {
+        let candidate = |street: &str, distance: f64| LocationCandidate {
+            location: LocationResult {
+                street: String::from(street),
+                house_number: None,
+                city: String::from("Karlsruhe"),
+                country: String::from("Deutschland"),
+            },
+            distance,
+        };
+
+        let nearest = nearest_candidates(
+            vec![
+                candidate("Kaiserstraße", 40.0),
+                candidate("Marktplatz", 10.0),
+                candidate("Kaiserstraße", 30.0),
+                candidate("Zirkel", 80.0),
+            ],
+            2,
+        );
+
+        let streets: Vec<(&str, f64)> = nearest
+            .iter()
+            .map(|c| (c.location.street.as_str(), c.distance))
+            .collect();
+        assert_eq!(streets, vec![("Marktplatz", 10.0), ("Kaiserstraße", 30.0)]);
+    }
+}
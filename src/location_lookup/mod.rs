@@ -1,12 +1,32 @@
 use std::fmt::{Display, Formatter};
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::{Error, Result};
-use geocoding::{DetailedReverse, Openstreetmap, Point};
 use serde::{Deserialize, Serialize};
-use std::env;
-use std::time::Duration;
 use tokio::sync::mpsc::Receiver;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::Instant;
+
+use crate::location_lookup::cache::GeocodingCache;
+use crate::location_lookup::geocoder::Geocoder;
+use crate::metrics;
+
+pub mod cache;
+pub mod geocoder;
+pub mod offline;
+
+/// How long a user waits for the address of a shared location, including the time in the queue.
+pub const LOOKUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Number of nearby addresses offered to the user.
+const MAX_CANDIDATES: usize = 5;
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// Pause before a died lookup worker is restarted, so a persistent panic doesn't spin.
+const RESTART_PAUSE: Duration = Duration::from_secs(1);
 
 type Responder<T> = oneshot::Sender<Result<T, Error>>;
 
@@ -14,10 +34,43 @@ type Responder<T> = oneshot::Sender<Result<T, Error>>;
 pub struct Lookup {
     pub longitude: f32,
     pub latitude: f32,
+    /// The lookup is answered with [`LookupError::Timeout`] after this, or skipped if it is
+    /// still queued.
+    pub deadline: Instant,
+
+    pub responder: Responder<Vec<LocationCandidate>>,
+}
 
-    pub responder: Responder<Option<LocationResult>>,
+/// Why a lookup got no address, besides errors of the geocoder itself.
+#[derive(Debug)]
+pub enum LookupError {
+    /// The deadline passed, e.g. because the geocoder is slow or the queue is long.
+    Timeout,
+    /// The lookup worker stopped or died before answering.
+    Stopped,
+    /// There is no address at the location.
+    NotFound,
+}
+
+impl LookupError {
+    /// The lookup error behind `error`, `None` for other errors.
+    pub fn of(error: &Error) -> Option<&LookupError> {
+        error.downcast_ref::<LookupError>()
+    }
 }
 
+impl Display for LookupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::Timeout => write!(f, "lookup timed out"),
+            LookupError::Stopped => write!(f, "lookup service stopped"),
+            LookupError::NotFound => write!(f, "no address found"),
+        }
+    }
+}
+
+impl std::error::Error for LookupError {}
+
 impl Display for Lookup {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -29,7 +82,7 @@ impl Display for Lookup {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LocationResult {
     pub street: String,
     pub house_number: Option<String>,
@@ -47,69 +100,228 @@ impl Display for LocationResult {
     }
 }
 
+/// An address near a location, candidates of a lookup are ordered by distance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationCandidate {
+    pub location: LocationResult,
+    /// Distance to the looked up location in meters.
+    pub distance: f64,
+}
+
+impl LocationCandidate {
+    /// Button label like "Kaiserstraße 12 (15 m)".
+    pub fn label(&self) -> String {
+        match &self.location.house_number {
+            Some(number) => format!(
+                "{} {} ({:.0} m)",
+                self.location.street, number, self.distance
+            ),
+            None => format!("{} ({:.0} m)", self.location.street, self.distance),
+        }
+    }
+}
+
+/// Distance in meters, flat earth is precise enough at the scale of a city.
+pub fn distance_meters(
+    longitude_a: f64,
+    latitude_a: f64,
+    longitude_b: f64,
+    latitude_b: f64,
+) -> f64 {
+    let x = (longitude_b - longitude_a).to_radians()
+        * ((latitude_a + latitude_b) / 2.0).to_radians().cos();
+    let y = (latitude_b - latitude_a).to_radians();
+    (x * x + y * y).sqrt() * EARTH_RADIUS_METERS
+}
+
 pub struct LocationLookup {
     receiver: Receiver<Lookup>,
+    geocoder: Arc<dyn Geocoder>,
+    cache: GeocodingCache,
 }
 
 impl LocationLookup {
-    pub async fn new(receiver: Receiver<Lookup>) -> LocationLookup {
-        LocationLookup { receiver }
+    pub async fn new(
+        receiver: Receiver<Lookup>,
+        geocoder: Arc<dyn Geocoder>,
+        cache: GeocodingCache,
+    ) -> LocationLookup {
+        LocationLookup {
+            receiver,
+            geocoder,
+            cache,
+        }
+    }
+
+    /// Runs the lookup worker and restarts it if it panics. Returns when all senders are gone.
+    pub async fn supervise(self) {
+        let lookup = Arc::new(Mutex::new(self));
+
+        loop {
+            let worker = lookup.clone();
+            match tokio::spawn(async move { worker.lock().await.start().await }).await {
+                Ok(()) => break,
+                Err(e) => {
+                    log::error!("Lookup worker died, restarting it: {}", e);
+                    metrics::GEOCODING_WORKER_RESTARTS.inc();
+                    tokio::time::sleep(RESTART_PAUSE).await;
+                }
+            }
+        }
     }
 
-    // async fn request(&self, longitude: f32, latitude: f32) -> Result<AddressDetails, Error> {
-    //     let response = self
-    //         .client
-    //         .get(&format!("{}reverse", self.endpoint))
-    //         .query(&[
-    //             (&"lon", &longitude.to_string()),
-    //             (&"lat", &latitude.to_string()),
-    //             (&"format", &String::from("geojson")),
-    //         ])
-    //         .send()
-    //         .await?
-    //         .error_for_status()?;
-    //
-    //     let mut result: OpenstreetmapResponse<f32> = response.json().await?;
-    //     let address = result.features.pop().unwrap().properties.address.unwrap();
-    //     Ok(address)
-    // }
-
-    pub async fn start(&mut self) {
+    async fn start(&mut self) {
         log::info!("Starting Lookup Service");
-        while let Some(lookup) = self.receiver.recv().await {
+        let cached = !self.geocoder.is_local();
+        let mut flush = tokio::time::interval(cache::FLUSH_INTERVAL);
+
+        loop {
+            let lookup = tokio::select! {
+                lookup = self.receiver.recv() => match lookup {
+                    Some(lookup) => lookup,
+                    None => break,
+                },
+                _ = flush.tick() => {
+                    self.cache.flush().await;
+                    continue;
+                }
+            };
             log::info!("Got Lookup Request: {}", lookup);
+            metrics::GEOCODING_QUEUE_DEPTH.dec();
 
-            let longitude = lookup.longitude;
-            let latitude = lookup.latitude;
-            let result =
-                tokio::task::spawn_blocking(move || match env::var("OPENSTREETMAP_ENDPOINT") {
-                    Ok(endpoint) => Openstreetmap::new_with_endpoint(endpoint)
-                        .detailed_reverse(&Point::new(longitude, latitude)),
-                    Err(_) => {
-                        Openstreetmap::new().detailed_reverse(&Point::new(longitude, latitude))
-                    }
-                })
-                .await
-                .expect("Task didn't finish.");
-
-            if let Err(e) = result {
-                lookup.responder.send(Err(Error::from(e))).unwrap();
-            } else if let Some(address) = result.unwrap() {
-                let result = LocationResult {
-                    city: address.city.unwrap_or_default(),
-                    country: address.country.unwrap_or_default(),
-                    house_number: address.house_number.map(|n| n.parse().unwrap()),
-                    street: address.road.unwrap_or_default(),
-                };
-                log::info!("Found location: {}", result);
-                lookup.responder.send(Ok(Some(result))).unwrap();
-            } else {
-                log::warn!("Didn't find anything: {}", lookup);
-                lookup.responder.send(Ok(None)).unwrap();
+            if lookup.responder.is_closed() || Instant::now() >= lookup.deadline {
+                log::warn!("Skipping lookup, nobody is waiting for it: {}", lookup);
+                metrics::GEOCODING_LOOKUPS
+                    .with_label_values(&["skipped"])
+                    .inc();
+                continue;
             }
 
-            tokio::time::sleep(Duration::from_secs(1u64)).await;
+            // Cached results didn't cost the geocoder a request, no need to throttle.
+            if let Some(result) = cached
+                .then(|| self.cache.get(lookup.longitude, lookup.latitude))
+                .flatten()
+            {
+                if lookup.responder.send(Ok(result)).is_err() {
+                    log::warn!("Nobody is waiting for the lookup result anymore");
+                }
+                continue;
+            }
+
+            let result = tokio::time::timeout_at(
+                lookup.deadline,
+                self.geocoder
+                    .reverse(lookup.longitude, lookup.latitude, MAX_CANDIDATES),
+            )
+            .await
+            .unwrap_or_else(|_| Err(Error::from(LookupError::Timeout)));
+
+            match &result {
+                Ok(candidates) if candidates.is_empty() => {
+                    log::warn!("Didn't find anything: {}", lookup);
+                    metrics::GEOCODING_LOOKUPS
+                        .with_label_values(&["not_found"])
+                        .inc();
+                }
+                Ok(candidates) => {
+                    log::info!(
+                        "Found location: {} and {} more candidates",
+                        candidates[0].location,
+                        candidates.len() - 1
+                    );
+                    metrics::GEOCODING_LOOKUPS
+                        .with_label_values(&["found"])
+                        .inc();
+                }
+                Err(e) if matches!(LookupError::of(e), Some(LookupError::Timeout)) => {
+                    log::warn!("Lookup timed out: {}", lookup);
+                    metrics::GEOCODING_LOOKUPS
+                        .with_label_values(&["timeout"])
+                        .inc();
+                }
+                Err(e) => {
+                    log::error!("Lookup failed: {}, {}", lookup, e);
+                    metrics::GEOCODING_LOOKUPS
+                        .with_label_values(&["error"])
+                        .inc();
+                }
+            }
+            if let (true, Ok(candidates)) = (cached, &result) {
+                self.cache
+                    .insert(lookup.longitude, lookup.latitude, candidates.clone());
+            }
+            if lookup.responder.send(result).is_err() {
+                log::warn!("Nobody is waiting for the lookup result anymore");
+            }
+
+            tokio::time::sleep(self.geocoder.throttle()).await;
         }
+        self.cache.flush().await;
         log::info!("Stopping Lookup Service");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::location_lookup::geocoder::MockGeocoder;
+
+    /// Looks up a location with a worker answering from `fixtures/geocoder.json`.
+    async fn lookup(longitude: f32, latitude: f32) -> Result<Vec<LocationCandidate>> {
+        let geocoder = MockGeocoder::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/geocoder.json"
+        ))
+        .unwrap();
+        let (sender, receiver) = mpsc::channel(1);
+        let worker = LocationLookup::new(
+            receiver,
+            Arc::new(geocoder),
+            GeocodingCache::new(None, 90, 10),
+        )
+        .await;
+        let worker = tokio::spawn(worker.supervise());
+
+        let (responder, response) = oneshot::channel();
+        sender
+            .send(Lookup {
+                longitude,
+                latitude,
+                deadline: Instant::now() + LOOKUP_TIMEOUT,
+                responder,
+            })
+            .await
+            .unwrap();
+        let result = response.await.unwrap();
+
+        drop(sender);
+        worker.await.unwrap();
+        result
+    }
+
+    #[tokio::test]
+    async fn finds_fixture_near_location() {
+        let candidates = lookup(8.4036, 49.0094).await.unwrap();
+
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(
+            candidates[0].location,
+            LocationResult {
+                street: String::from("Marktplatz"),
+                house_number: None,
+                city: String::from("Karlsruhe"),
+                country: String::from("Deutschland"),
+            }
+        );
+        assert!(candidates[0].distance < 10.0);
+    }
+
+    #[tokio::test]
+    async fn finds_nothing_far_from_fixtures() {
+        let candidates = lookup(8.4500, 49.0300).await.unwrap();
+
+        assert!(candidates.is_empty());
+    }
+}
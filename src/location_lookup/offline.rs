@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::location_lookup::geocoder::Geocoder;
+use crate::location_lookup::{distance_meters, LocationCandidate, LocationResult};
+
+/// Size of a grid cell in degrees, about 110 m north-south and 70 m east-west in Karlsruhe.
+const CELL_SIZE: f64 = 0.001;
+
+/// Addresses further away aren't where the user is, e.g. a location in a park.
+const MAX_DISTANCE_METERS: f64 = 100.0;
+
+#[derive(Debug, Deserialize)]
+struct Feature {
+    geometry: Option<Value>,
+    #[serde(default)]
+    properties: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FeatureCollection {
+    features: Vec<Feature>,
+}
+
+#[derive(Debug)]
+struct AddressPoint {
+    longitude: f64,
+    latitude: f64,
+    result: LocationResult,
+}
+
+fn cell(longitude: f64, latitude: f64) -> (i64, i64) {
+    (
+        (longitude / CELL_SIZE).floor() as i64,
+        (latitude / CELL_SIZE).floor() as i64,
+    )
+}
+
+/// Position of a feature, the average of the outer ring for buildings.
+fn position(geometry: &Value) -> Option<(f64, f64)> {
+    let point =
+        |coordinates: &Value| Some((coordinates.get(0)?.as_f64()?, coordinates.get(1)?.as_f64()?));
+
+    match geometry.get("type")?.as_str()? {
+        "Point" => point(geometry.get("coordinates")?),
+        "Polygon" => {
+            let ring = geometry.get("coordinates")?.get(0)?.as_array()?;
+            let points: Vec<(f64, f64)> = ring.iter().filter_map(point).collect();
+            if points.is_empty() {
+                return None;
+            }
+            let count = points.len() as f64;
+            Some((
+                points.iter().map(|p| p.0).sum::<f64>() / count,
+                points.iter().map(|p| p.1).sum::<f64>() / count,
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn tag(properties: &HashMap<String, Value>, key: &str) -> Option<String> {
+    properties
+        .get(key)
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+/// Answers lookups from the address points of a local OpenStreetMap extract, without any
+/// external service.
+///
+/// The extract is a GeoJSON feature collection of points or building polygons with the OSM tags
+/// `addr:street`, `addr:housenumber`, `addr:city` and `addr:country` as properties, e.g. created
+/// with `osmium tags-filter karlsruhe.osm.pbf nwr/addr:housenumber -o addresses.osm.pbf` and
+/// `osmium export addresses.osm.pbf -o addresses.geojson`. Features without a street are skipped.
+pub struct OfflineGeocoder {
+    points: Vec<AddressPoint>,
+    /// Indices into `points` by grid cell.
+    grid: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl OfflineGeocoder {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let collection: FeatureCollection = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| Error::msg(format!("invalid address extract: {}", e)))?;
+
+        let points: Vec<AddressPoint> = collection
+            .features
+            .into_iter()
+            .filter_map(|feature| {
+                let (longitude, latitude) = position(feature.geometry.as_ref()?)?;
+                Some(AddressPoint {
+                    longitude,
+                    latitude,
+                    result: LocationResult {
+                        street: tag(&feature.properties, "addr:street")?,
+                        house_number: tag(&feature.properties, "addr:housenumber"),
+                        city: tag(&feature.properties, "addr:city").unwrap_or_default(),
+                        country: tag(&feature.properties, "addr:country").unwrap_or_default(),
+                    },
+                })
+            })
+            .collect();
+
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, point) in points.iter().enumerate() {
+            grid.entry(cell(point.longitude, point.latitude))
+                .or_default()
+                .push(i);
+        }
+
+        log::info!("Loaded {} address points from {}", points.len(), path);
+        Ok(OfflineGeocoder { points, grid })
+    }
+
+    /// Up to `limit` address points within `MAX_DISTANCE_METERS`, nearest first.
+    fn nearest(&self, longitude: f64, latitude: f64, limit: usize) -> Vec<LocationCandidate> {
+        let (x, y) = cell(longitude, latitude);
+
+        // A cell is at least 70 m wide, two cells in every direction cover the maximum distance.
+        let mut candidates: Vec<LocationCandidate> = (x - 2..=x + 2)
+            .flat_map(|x| (y - 2..=y + 2).map(move |y| (x, y)))
+            .filter_map(|cell| self.grid.get(&cell))
+            .flatten()
+            .map(|&i| {
+                let point = &self.points[i];
+                LocationCandidate {
+                    location: point.result.clone(),
+                    distance: distance_meters(longitude, latitude, point.longitude, point.latitude),
+                }
+            })
+            .filter(|candidate| candidate.distance <= MAX_DISTANCE_METERS)
+            .collect();
+        candidates.sort_by(|a, b| {
+            a.distance
+                .partial_cmp(&b.distance)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        candidates.truncate(limit);
+
+        candidates
+    }
+}
+
+#[async_trait]
+impl Geocoder for OfflineGeocoder {
+    async fn reverse(
+        &self,
+        longitude: f32,
+        latitude: f32,
+        limit: usize,
+    ) -> Result<Vec<LocationCandidate>> {
+        Ok(self.nearest(longitude as f64, latitude as f64, limit))
+    }
+
+    fn throttle(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geocoder() -> OfflineGeocoder {
+        OfflineGeocoder::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/addresses.geojson"
+        ))
+        .unwrap()
+    }
+
+    fn addresses(candidates: &[LocationCandidate]) -> Vec<String> {
+        candidates
+            .iter()
+            .map(|candidate| {
+                format!(
+                    "{} {}",
+                    candidate.location.street,
+                    candidate.location.house_number.as_deref().unwrap_or("")
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn skips_features_without_street() {
+        assert_eq!(geocoder().points.len(), 7);
+    }
+
+    #[test]
+    fn finds_nearest_address_at_marktplatz() {
+        let candidates = geocoder().nearest(8.4037, 49.0094, 1);
+
+        assert_eq!(addresses(&candidates), vec!["Marktplatz 1"]);
+        assert_eq!(candidates[0].location.city, "Karlsruhe");
+        assert!((candidates[0].distance - 11.1).abs() < 0.5);
+    }
+
+    #[test]
+    fn orders_candidates_by_distance() {
+        let candidates = geocoder().nearest(8.4037, 49.0094, 5);
+
+        // Karl-Friedrich-Straße 2 and Waldstraße 3 are a bit more than 100 m away.
+        assert_eq!(
+            addresses(&candidates),
+            vec![
+                "Marktplatz 1",
+                "Marktplatz 7",
+                "Kaiserstraße 133",
+                "Kaiserstraße 146"
+            ]
+        );
+        assert!(candidates
+            .windows(2)
+            .all(|pair| pair[0].distance <= pair[1].distance));
+    }
+
+    #[test]
+    fn finds_nothing_beyond_max_distance() {
+        assert!(geocoder().nearest(8.4100, 49.0094, 5).is_empty());
+    }
+}
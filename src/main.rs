@@ -1,12 +1,33 @@
 mod bot_logic;
+mod calendar;
+mod health;
+mod importer;
 mod location_lookup;
+mod metrics;
+mod storage;
 pub mod trash_dates;
+mod trash_types;
+mod webhook;
 use crate::bot_logic::Bot;
 use log::info;
+use std::env;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    info!("Starting Bot...");
-    Bot::start().await;
+
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        Some("import") => {
+            info!("Importing waste calendar...");
+            if let Err(e) = importer::run(args.collect(), storage::from_env()).await {
+                log::error!("Import failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        _ => {
+            info!("Starting Bot...");
+            Bot::start().await;
+        }
+    }
 }
@@ -0,0 +1,146 @@
+use std::convert::Infallible;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Encoder, Histogram, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+
+use crate::health::Health;
+
+lazy_static! {
+    pub static ref TELEGRAM_MESSAGES_SENT: IntCounter = register_int_counter!(
+        "trash_bot_telegram_messages_sent_total",
+        "Telegram messages sent successfully"
+    )
+    .unwrap();
+    pub static ref TELEGRAM_MESSAGES_FAILED: IntCounter = register_int_counter!(
+        "trash_bot_telegram_messages_failed_total",
+        "Telegram messages that could not be sent after all retries"
+    )
+    .unwrap();
+    pub static ref GRAPHQL_REQUEST_DURATION: Histogram = register_histogram!(
+        "trash_bot_graphql_request_duration_seconds",
+        "Duration of GraphQL requests"
+    )
+    .unwrap();
+    pub static ref GRAPHQL_ERRORS: IntCounter = register_int_counter!(
+        "trash_bot_graphql_errors_total",
+        "GraphQL requests that failed, including network errors and error responses"
+    )
+    .unwrap();
+    pub static ref GEOCODING_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "trash_bot_geocoding_lookups_total",
+        "Reverse geocoding lookups by result (found, not_found, timeout, skipped, error)",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref GEOCODING_CACHE: IntCounterVec = register_int_counter_vec!(
+        "trash_bot_geocoding_cache_total",
+        "Reverse geocoding cache lookups by result (hit, miss)",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref GEOCODING_WORKER_RESTARTS: IntCounter = register_int_counter!(
+        "trash_bot_geocoding_worker_restarts_total",
+        "Restarts of the location lookup worker after it died"
+    )
+    .unwrap();
+    pub static ref GEOCODING_QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "trash_bot_geocoding_queue_depth",
+        "Lookups waiting for the location lookup service"
+    )
+    .unwrap();
+    pub static ref DIALOGUE_TRANSITIONS: IntCounterVec = register_int_counter_vec!(
+        "trash_bot_dialogue_transitions_total",
+        "Dialogue steps by the state they lead to",
+        &["state"]
+    )
+    .unwrap();
+    pub static ref SCHEDULER_RUN_DURATION: Histogram = register_histogram!(
+        "trash_bot_scheduler_run_duration_seconds",
+        "Duration of a scheduler run, checking and sending the reminders of the due minutes"
+    )
+    .unwrap();
+}
+
+/// Address of the metrics and health probe server, `None` if `METRICS_ADDRESS` isn't set.
+pub fn address_from_env() -> Option<SocketAddr> {
+    env::var("METRICS_ADDRESS").ok().map(|address| {
+        address
+            .parse()
+            .expect("METRICS_ADDRESS is not a valid socket address")
+    })
+}
+
+fn metrics_response() -> Response<Body> {
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+
+    match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(_) => Response::builder()
+            .header(CONTENT_TYPE, encoder.format_type())
+            .body(Body::from(buffer))
+            .unwrap(),
+        Err(e) => {
+            log::error!("Could not encode metrics: {}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap()
+        }
+    }
+}
+
+fn probe_response(result: Result<(), String>) -> Response<Body> {
+    match result {
+        Ok(_) => Response::new(Body::from("ok")),
+        Err(e) => {
+            log::warn!("Health check failed: {}", e);
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(Body::from(e))
+                .unwrap()
+        }
+    }
+}
+
+async fn handle_request(
+    health: Arc<Health>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    Ok(match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => metrics_response(),
+        (&Method::GET, "/health/live") => probe_response(health.live()),
+        (&Method::GET, "/health/ready") => probe_response(health.ready().await),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap(),
+    })
+}
+
+/// Serves the metrics in the Prometheus text format at `/metrics`, and the liveness and
+/// readiness probes at `/health/live` and `/health/ready`.
+pub async fn serve(address: SocketAddr, health: Arc<Health>) {
+    let make_service = make_service_fn(move |_| {
+        let health = health.clone();
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(health.clone(), request)
+            }))
+        }
+    });
+
+    log::info!("Serving metrics on {}", address);
+    if let Err(e) = Server::bind(&address).serve(make_service).await {
+        log::error!("Metrics server failed: {}", e);
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt::{Display, Formatter};
+
+use reqwest::StatusCode;
+use rusqlite::ErrorCode;
+
+/// Why a storage request failed. Returned by the storage backends wrapped in an
+/// [`anyhow::Error`], callers that need to tell failures apart downcast to it, see
+/// [`StorageError::of`].
+#[derive(Debug)]
+pub enum StorageError {
+    /// The request could not be sent or the connection broke, e.g. because Hasura is down.
+    Network(reqwest::Error),
+    /// The server answered with an error status.
+    Status(StatusCode),
+    /// The query was answered with GraphQL errors.
+    GraphQl(Vec<graphql_client::Error>),
+    /// The requested row doesn't exist, e.g. "street" or "user".
+    NotFound(&'static str),
+    /// The response could not be decoded or contained no data.
+    Decode(String),
+    /// A query of the embedded SQLite database failed.
+    Sqlite(rusqlite::Error),
+}
+
+impl StorageError {
+    /// The storage error behind `error`, `None` for other errors.
+    pub fn of(error: &anyhow::Error) -> Option<&StorageError> {
+        error.downcast_ref::<StorageError>()
+    }
+
+    /// Whether the failure is likely gone after a while, e.g. Hasura restarting.
+    pub fn is_temporary(&self) -> bool {
+        match self {
+            StorageError::Network(_) => true,
+            StorageError::Status(status) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            StorageError::Sqlite(rusqlite::Error::SqliteFailure(error, _)) => matches!(
+                error.code,
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Formats the path of a GraphQL error like "search_streets.0.id".
+fn error_path(error: &graphql_client::Error) -> String {
+    error
+        .path
+        .as_ref()
+        .map(|path| {
+            path.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(".")
+        })
+        .unwrap_or_else(|| String::from("<query>"))
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Network(e) => write!(f, "request failed: {}", e),
+            StorageError::Status(status) => write!(f, "unexpected response status {}", status),
+            StorageError::GraphQl(errors) => write!(
+                f,
+                "graphql errors: {}",
+                errors
+                    .iter()
+                    .map(|error| format!("{}: {}", error_path(error), error.message))
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            ),
+            StorageError::NotFound(what) => write!(f, "{} not found", what),
+            StorageError::Decode(e) => write!(f, "could not decode response: {}", e),
+            StorageError::Sqlite(e) => write!(f, "sqlite query failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Network(e) => Some(e),
+            StorageError::Sqlite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(error: rusqlite::Error) -> Self {
+        match error {
+            rusqlite::Error::QueryReturnedNoRows => StorageError::NotFound("row"),
+            error => StorageError::Sqlite(error),
+        }
+    }
+}
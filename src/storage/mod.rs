@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+use crate::storage::sqlite::SqliteStorage;
+use crate::trash_dates::{
+    Address, ReminderCounts, RequestPerformer, Statistics, Street, TrashDate, User,
+};
+use crate::trash_types::TrashTypeInfo;
+
+pub use error::StorageError;
+
+mod error;
+pub mod sqlite;
+
+/// Everything the bot needs to read or write user and trash date data.
+///
+/// Implemented by the Hasura GraphQL client ([`RequestPerformer`]) and by an embedded SQLite
+/// database ([`SqliteStorage`]). The backend is selected at startup by [`from_env`].
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn get_tomorrows_trash(&self, user_id: i64) -> Result<Vec<TrashDate>>;
+
+    /// All dates of a user from today on, ordered by date.
+    async fn get_upcoming_trash(&self, user_id: i64) -> Result<Vec<TrashDate>>;
+
+    /// All dates of a user between `from` and `to` (both inclusive), ordered by date.
+    async fn get_trash_between(
+        &self,
+        user_id: i64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<TrashDate>>;
+
+    /// The next date of every trash type a user has upcoming dates for, ordered by date.
+    async fn get_next_per_type(&self, user_id: i64) -> Result<Vec<TrashDate>>;
+
+    async fn get_street_id(&self, street_name: String) -> Result<i64>;
+
+    async fn get_notification_status(&self, telegram_chat_id: i64) -> Result<bool>;
+
+    async fn get_my_user_data(&self, telegram_chat_id: i64) -> Result<HashMap<String, String>>;
+
+    async fn search_similar_streets(&self, street_name: String) -> Result<Vec<Street>>;
+
+    async fn remove_user_data(&self, telegram_chat_id: i64) -> Result<bool>;
+
+    /// Saves an address profile under `name`, replacing a profile with the same name.
+    /// Registers the chat if it isn't registered yet.
+    async fn add_address(
+        &self,
+        telegram_chat_id: i64,
+        name: String,
+        street: i64,
+        house_number: Option<String>,
+    ) -> Result<()>;
+
+    /// Names of all address profiles of a chat, ordered by name.
+    async fn get_addresses(&self, telegram_chat_id: i64) -> Result<Vec<String>>;
+
+    async fn remove_address(&self, telegram_chat_id: i64, name: String) -> Result<bool>;
+
+    /// Turns notifications on or off as chosen by the user.
+    async fn set_notification(&self, telegram_chat_id: i64, notifications: bool) -> Result<bool>;
+
+    /// Turns the notifications of a chat off because the bot can't reach it anymore, or on again
+    /// once it can. Only notifications that were turned off this way are turned on again, those
+    /// the user turned off stay off. Returns whether the notifications changed.
+    async fn set_unreachable(&self, telegram_chat_id: i64, unreachable: bool) -> Result<bool>;
+
+    /// Users with notifications enabled, a notification time of `time` and at least one pickup
+    /// at `date`. Only the dates at `date` are returned.
+    async fn get_users_due(&self, date: NaiveDate, time: NaiveTime) -> Result<Vec<User>>;
+
+    /// Records the reminders of `trash_types` picked up at `date` as sent to a chat. Returns the
+    /// trash types that weren't recorded before, only their reminders may be sent.
+    async fn claim_notifications(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+        trash_types: Vec<String>,
+    ) -> Result<Vec<String>>;
+
+    /// Forgets claimed reminders that could not be sent, so a later run sends them again.
+    async fn release_notifications(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+        trash_types: Vec<String>,
+    ) -> Result<()>;
+
+    /// Forgets the sent and failed reminders of pickups before `date`, returns how many were
+    /// forgotten.
+    async fn prune_sent_notifications(&self, before: NaiveDate) -> Result<usize>;
+
+    /// Records that the reminder of the pickups at `date` could not be sent to a chat.
+    async fn record_failed_notification(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+    ) -> Result<()>;
+
+    /// Reminder messages sent and failed from `from` (inclusive) to `to` (exclusive). A message
+    /// counts as sent when its reminders were claimed and not released again.
+    async fn get_reminder_counts(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<ReminderCounts>;
+
+    async fn set_notification_time(
+        &self,
+        telegram_chat_id: i64,
+        notification_time: NaiveTime,
+    ) -> Result<NaiveTime>;
+
+    /// Language code chosen by a chat, `None` if the chat didn't choose one.
+    async fn get_language(&self, telegram_chat_id: i64) -> Result<Option<String>>;
+
+    /// Stores the language code of a chat, registers the chat if it isn't registered yet.
+    async fn set_language(&self, telegram_chat_id: i64, language: String) -> Result<()>;
+
+    /// All known trash types with their display metadata, ordered by name.
+    async fn get_trash_types(&self) -> Result<Vec<TrashTypeInfo>>;
+
+    /// Trash types a chat doesn't want to be reminded of or see in manual requests.
+    async fn get_unsubscribed_trash_types(&self, telegram_chat_id: i64) -> Result<Vec<String>>;
+
+    /// Subscribes to or unsubscribes from a trash type, returns the new subscription state.
+    async fn set_trash_type_subscription(
+        &self,
+        telegram_chat_id: i64,
+        trash_type: String,
+        subscribed: bool,
+    ) -> Result<bool>;
+
+    /// Counts of users with at least one address, their enabled notifications and users per
+    /// street.
+    async fn get_statistics(&self) -> Result<Statistics>;
+
+    async fn get_registered_addresses(&self) -> Result<Vec<Address>>;
+
+    /// Stores streets as `(karlsruhe_id, name)` pairs, known ids get the new name. Returns the
+    /// number of stored streets.
+    async fn upsert_streets(&self, streets: Vec<(i64, String)>) -> Result<usize>;
+
+    /// Inserts the dates of an address, dates that are already stored are left untouched.
+    /// Returns the number of newly inserted dates.
+    async fn upsert_dates(&self, address: &Address, dates: Vec<TrashDate>) -> Result<usize>;
+}
+
+/// Creates the storage backend selected by `STORAGE_BACKEND` (`hasura` or `sqlite`).
+pub fn from_env() -> Arc<dyn Storage> {
+    match env::var("STORAGE_BACKEND")
+        .unwrap_or_else(|_| String::from("hasura"))
+        .as_str()
+    {
+        "sqlite" => {
+            let path = env::var("SQLITE_PATH").unwrap_or_else(|_| String::from("trash_bot.sqlite"));
+            log::info!("Using sqlite storage at {}", path);
+
+            Arc::new(SqliteStorage::open(&path).expect("Failed to open sqlite database"))
+        }
+        "hasura" => {
+            log::info!("Using hasura storage");
+            Arc::new(RequestPerformer::from_env())
+        }
+        other => panic!(
+            "Unknown storage backend '{}', set env variable 'STORAGE_BACKEND' to 'hasura' or 'sqlite'.",
+            other
+        ),
+    }
+}
@@ -0,0 +1,916 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::storage::{Storage, StorageError};
+use crate::trash_dates::{
+    sentence_to_uppercase, today, Address, ReminderCounts, Statistics, Street, TrashDate,
+    TrashType, User,
+};
+use crate::trash_types::TrashTypeInfo;
+
+/// Schema migrations, applied in order. `PRAGMA user_version` stores how many have been applied.
+const MIGRATIONS: &[&str] = &[
+    "
+CREATE TABLE IF NOT EXISTS streets (
+    id INTEGER PRIMARY KEY,
+    karlsruhe_id INTEGER NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS trash_types (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS users (
+    telegram_chat_id INTEGER PRIMARY KEY,
+    street INTEGER NOT NULL REFERENCES streets (id),
+    house_number TEXT,
+    enabled_notifications INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS dates (
+    id INTEGER PRIMARY KEY,
+    date TEXT NOT NULL,
+    trash_type INTEGER NOT NULL REFERENCES trash_types (id),
+    user_id INTEGER NOT NULL REFERENCES users (telegram_chat_id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (trash_type, date, user_id)
+);
+
+INSERT OR IGNORE INTO trash_types (name) VALUES ('Bioabfall'), ('Wertstoff'), ('Papier'), ('Restmüll');
+",
+    "ALTER TABLE users ADD COLUMN notification_time TEXT NOT NULL DEFAULT '16:00:00';",
+    "
+CREATE TABLE addresses (
+    id INTEGER PRIMARY KEY,
+    telegram_chat_id INTEGER NOT NULL REFERENCES users (telegram_chat_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    street INTEGER NOT NULL REFERENCES streets (id),
+    house_number TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (telegram_chat_id, name)
+);
+
+INSERT INTO addresses (telegram_chat_id, name, street, house_number, created_at)
+SELECT telegram_chat_id, 'Zuhause', street, house_number, created_at FROM users;
+
+CREATE TABLE users_new (
+    telegram_chat_id INTEGER PRIMARY KEY,
+    enabled_notifications INTEGER NOT NULL DEFAULT 1,
+    notification_time TEXT NOT NULL DEFAULT '16:00:00',
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO users_new (telegram_chat_id, enabled_notifications, notification_time, created_at)
+SELECT telegram_chat_id, enabled_notifications, notification_time, created_at FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
+
+CREATE TABLE dates_new (
+    id INTEGER PRIMARY KEY,
+    date TEXT NOT NULL,
+    trash_type INTEGER NOT NULL REFERENCES trash_types (id),
+    user_id INTEGER NOT NULL REFERENCES users (telegram_chat_id) ON DELETE CASCADE,
+    address_id INTEGER NOT NULL REFERENCES addresses (id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (trash_type, date, address_id)
+);
+
+INSERT INTO dates_new (id, date, trash_type, user_id, address_id, created_at)
+SELECT d.id, d.date, d.trash_type, d.user_id, a.id, d.created_at FROM dates d
+JOIN addresses a ON a.telegram_chat_id = d.user_id;
+
+DROP TABLE dates;
+ALTER TABLE dates_new RENAME TO dates;
+",
+    "
+CREATE TABLE unsubscribed_trash_types (
+    telegram_chat_id INTEGER NOT NULL REFERENCES users (telegram_chat_id) ON DELETE CASCADE,
+    trash_type INTEGER NOT NULL REFERENCES trash_types (id),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (telegram_chat_id, trash_type)
+);
+",
+    "ALTER TABLE users ADD COLUMN language TEXT;",
+    "
+CREATE TABLE sent_notifications (
+    telegram_chat_id INTEGER NOT NULL REFERENCES users (telegram_chat_id) ON DELETE CASCADE,
+    date TEXT NOT NULL,
+    trash_type TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (telegram_chat_id, date, trash_type)
+);
+",
+    "
+ALTER TABLE trash_types ADD COLUMN emoji TEXT;
+ALTER TABLE trash_types ADD COLUMN colour TEXT;
+ALTER TABLE trash_types ADD COLUMN description TEXT;
+",
+    "
+CREATE TABLE failed_notifications (
+    id INTEGER PRIMARY KEY,
+    telegram_chat_id INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+",
+    "ALTER TABLE users ADD COLUMN notifications_auto_disabled INTEGER NOT NULL DEFAULT 0;",
+];
+
+/// Format of `CURRENT_TIMESTAMP`, timestamps are compared as text.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Embedded storage backend, keeps all data in a single SQLite file.
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        migrate(&connection)?;
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+        Ok(SqliteStorage {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Runs `f` on the blocking thread pool, rusqlite doesn't provide an async interface. Errors
+    /// are returned as [`StorageError`], a query without a result row as `NotFound`.
+    async fn with_connection<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection = self.connection.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let connection = connection
+                .lock()
+                .map_err(|_| Error::msg("sqlite connection lock poisoned"))?;
+            f(&connection).map_err(|e| Error::from(StorageError::from(e)))
+        })
+        .await?
+    }
+}
+
+/// Applies all pending migrations, each in its own transaction. Must run before foreign keys
+/// are enabled, migrations that recreate a table would otherwise cascade deletes.
+fn migrate(connection: &Connection) -> Result<()> {
+    let version: usize =
+        connection.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        log::info!("Applying sqlite migration {}", i + 1);
+        connection.execute_batch(&format!(
+            "BEGIN;\n{}\nPRAGMA user_version = {};\nCOMMIT;",
+            migration,
+            i + 1
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Maps a `(date, trash type name, address name)` row to a trash date.
+fn trash_date_from_row(row: &Row) -> rusqlite::Result<TrashDate> {
+    let name: String = row.get(1)?;
+    Ok(TrashDate {
+        date: row.get(0)?,
+        trash_type: TrashType::from(&name[..]),
+        name,
+        address: row.get(2)?,
+    })
+}
+
+/// Groups `(chat id, date, trash type name, address name)` rows, ordered by chat id, into users.
+fn rows_to_users(rows: Vec<(i64, NaiveDate, String, String)>) -> Vec<User> {
+    let mut users: Vec<User> = Vec::new();
+
+    for (client_id, date, name, address) in rows {
+        let trash_date = TrashDate {
+            trash_type: TrashType::from(&name[..]),
+            date,
+            name,
+            address: Some(address),
+        };
+
+        match users.last_mut() {
+            Some(user) if user.client_id == client_id => user.dates.push(trash_date),
+            _ => users.push(User {
+                client_id,
+                dates: vec![trash_date],
+            }),
+        }
+    }
+
+    users
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn get_tomorrows_trash(&self, user_id: i64) -> Result<Vec<TrashDate>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT d.date, t.name, a.name FROM dates d
+                 JOIN trash_types t ON t.id = d.trash_type
+                 JOIN addresses a ON a.id = d.address_id
+                 WHERE d.user_id = ?1 AND d.date = ?2",
+            )?;
+            let rows =
+                statement.query_map(params![user_id, today().succ()], trash_date_from_row)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_upcoming_trash(&self, user_id: i64) -> Result<Vec<TrashDate>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT d.date, t.name, a.name FROM dates d
+                 JOIN trash_types t ON t.id = d.trash_type
+                 JOIN addresses a ON a.id = d.address_id
+                 WHERE d.user_id = ?1 AND d.date >= ?2
+                 ORDER BY d.date",
+            )?;
+            let rows = statement.query_map(params![user_id, today()], trash_date_from_row)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_trash_between(
+        &self,
+        user_id: i64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<TrashDate>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT d.date, t.name, a.name FROM dates d
+                 JOIN trash_types t ON t.id = d.trash_type
+                 JOIN addresses a ON a.id = d.address_id
+                 WHERE d.user_id = ?1 AND d.date BETWEEN ?2 AND ?3
+                 ORDER BY d.date",
+            )?;
+            let rows = statement.query_map(params![user_id, from, to], trash_date_from_row)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_next_per_type(&self, user_id: i64) -> Result<Vec<TrashDate>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT min(d.date) AS next_date, t.name, a.name FROM dates d
+                 JOIN trash_types t ON t.id = d.trash_type
+                 JOIN addresses a ON a.id = d.address_id
+                 WHERE d.user_id = ?1 AND d.date >= ?2
+                 GROUP BY a.id, t.id
+                 ORDER BY next_date",
+            )?;
+            let rows = statement.query_map(params![user_id, today()], trash_date_from_row)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_street_id(&self, street_name: String) -> Result<i64> {
+        self.search_similar_streets(street_name)
+            .await?
+            .into_iter()
+            .next()
+            .map(|street| street.id)
+            .ok_or_else(|| StorageError::NotFound("street").into())
+    }
+
+    async fn get_notification_status(&self, telegram_chat_id: i64) -> Result<bool> {
+        self.with_connection(move |connection| {
+            connection
+                .query_row(
+                    "SELECT enabled_notifications FROM users WHERE telegram_chat_id = ?1",
+                    params![telegram_chat_id],
+                    |row| row.get(0),
+                )
+                .optional()
+        })
+        .await?
+        .ok_or_else(|| StorageError::NotFound("user").into())
+    }
+
+    async fn get_my_user_data(&self, telegram_chat_id: i64) -> Result<HashMap<String, String>> {
+        self.with_connection(move |connection| {
+            let map = connection
+                .query_row(
+                    "SELECT created_at, enabled_notifications, notification_time, language
+                     FROM users WHERE telegram_chat_id = ?1",
+                    params![telegram_chat_id],
+                    |row| {
+                        let mut map: HashMap<String, String> = HashMap::new();
+                        map.insert("created_at".to_string(), row.get(0)?);
+                        map.insert(
+                            "enabled_notifications".to_string(),
+                            row.get::<_, bool>(1)?.to_string(),
+                        );
+                        map.insert(
+                            "notification_time".to_string(),
+                            row.get::<_, NaiveTime>(2)?.format("%H:%M").to_string(),
+                        );
+                        if let Some(language) = row.get::<_, Option<String>>(3)? {
+                            map.insert("language".to_string(), language);
+                        }
+                        map.insert("chat_id".to_string(), telegram_chat_id.to_string());
+                        Ok(map)
+                    },
+                )
+                .optional()?;
+
+            let mut map = match map {
+                Some(map) => map,
+                None => return Ok(None),
+            };
+
+            let mut statement = connection.prepare(
+                "SELECT a.name, s.name, a.house_number FROM addresses a
+                 JOIN streets s ON s.id = a.street
+                 WHERE a.telegram_chat_id = ?1",
+            )?;
+            let mut rows = statement.query(params![telegram_chat_id])?;
+            while let Some(row) = rows.next()? {
+                map.insert(
+                    format!("address ({})", row.get::<_, String>(0)?),
+                    format!(
+                        "{} {}",
+                        sentence_to_uppercase(&row.get::<_, String>(1)?),
+                        row.get::<_, Option<String>>(2)?.unwrap_or_default()
+                    )
+                    .trim_end()
+                    .to_string(),
+                );
+            }
+
+            let mut statement = connection.prepare(
+                "SELECT t.name FROM unsubscribed_trash_types u
+                 JOIN trash_types t ON t.id = u.trash_type
+                 WHERE u.telegram_chat_id = ?1
+                 ORDER BY t.name",
+            )?;
+            let unsubscribed = statement
+                .query_map(params![telegram_chat_id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            if !unsubscribed.is_empty() {
+                map.insert(
+                    "unsubscribed_trash_types".to_string(),
+                    unsubscribed.join(", "),
+                );
+            }
+
+            Ok(Some(map))
+        })
+        .await?
+        .ok_or_else(|| StorageError::NotFound("user").into())
+    }
+
+    async fn search_similar_streets(&self, street_name: String) -> Result<Vec<Street>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT id, name FROM streets
+                 WHERE name LIKE '%' || ?1 || '%'
+                 ORDER BY length(name), name
+                 LIMIT 5",
+            )?;
+            let rows = statement.query_map(params![street_name.trim()], |row| {
+                Ok(Street {
+                    id: row.get(0)?,
+                    street: sentence_to_uppercase(&row.get::<_, String>(1)?),
+                })
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn remove_user_data(&self, telegram_chat_id: i64) -> Result<bool> {
+        let affected_rows = self
+            .with_connection(move |connection| {
+                connection.execute(
+                    "DELETE FROM users WHERE telegram_chat_id = ?1",
+                    params![telegram_chat_id],
+                )
+            })
+            .await?;
+
+        Ok(affected_rows == 1)
+    }
+
+    async fn add_address(
+        &self,
+        telegram_chat_id: i64,
+        name: String,
+        street: i64,
+        house_number: Option<String>,
+    ) -> Result<()> {
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO users (telegram_chat_id, enabled_notifications) VALUES (?1, 1)
+                 ON CONFLICT (telegram_chat_id) DO NOTHING",
+                params![telegram_chat_id],
+            )?;
+            connection.execute(
+                "INSERT INTO addresses (telegram_chat_id, name, street, house_number)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (telegram_chat_id, name)
+                 DO UPDATE SET street = excluded.street, house_number = excluded.house_number",
+                params![
+                    telegram_chat_id,
+                    name,
+                    street,
+                    house_number.unwrap_or_default()
+                ],
+            )
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_addresses(&self, telegram_chat_id: i64) -> Result<Vec<String>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection
+                .prepare("SELECT name FROM addresses WHERE telegram_chat_id = ?1 ORDER BY name")?;
+            let rows = statement.query_map(params![telegram_chat_id], |row| row.get(0))?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn remove_address(&self, telegram_chat_id: i64, name: String) -> Result<bool> {
+        let affected_rows = self
+            .with_connection(move |connection| {
+                connection.execute(
+                    "DELETE FROM addresses WHERE telegram_chat_id = ?1 AND name = ?2",
+                    params![telegram_chat_id, name],
+                )
+            })
+            .await?;
+
+        Ok(affected_rows == 1)
+    }
+
+    async fn set_notification(&self, telegram_chat_id: i64, notifications: bool) -> Result<bool> {
+        let affected_rows = self
+            .with_connection(move |connection| {
+                connection.execute(
+                    "UPDATE users SET enabled_notifications = ?2, notifications_auto_disabled = 0
+                     WHERE telegram_chat_id = ?1",
+                    params![telegram_chat_id, notifications],
+                )
+            })
+            .await?;
+
+        if affected_rows == 0 {
+            return Err(StorageError::NotFound("user").into());
+        }
+
+        Ok(notifications)
+    }
+
+    async fn set_unreachable(&self, telegram_chat_id: i64, unreachable: bool) -> Result<bool> {
+        let affected_rows = self
+            .with_connection(move |connection| {
+                if unreachable {
+                    connection.execute(
+                        "UPDATE users SET enabled_notifications = 0, notifications_auto_disabled = 1
+                         WHERE telegram_chat_id = ?1 AND enabled_notifications = 1",
+                        params![telegram_chat_id],
+                    )
+                } else {
+                    connection.execute(
+                        "UPDATE users SET enabled_notifications = 1, notifications_auto_disabled = 0
+                         WHERE telegram_chat_id = ?1 AND notifications_auto_disabled = 1",
+                        params![telegram_chat_id],
+                    )
+                }
+            })
+            .await?;
+
+        Ok(affected_rows > 0)
+    }
+
+    async fn get_users_due(&self, date: NaiveDate, time: NaiveTime) -> Result<Vec<User>> {
+        let rows = self
+            .with_connection(move |connection| {
+                let mut statement = connection.prepare(
+                    "SELECT u.telegram_chat_id, d.date, t.name, a.name FROM users u
+                     JOIN dates d ON d.user_id = u.telegram_chat_id
+                     JOIN trash_types t ON t.id = d.trash_type
+                     JOIN addresses a ON a.id = d.address_id
+                     WHERE u.enabled_notifications = 1 AND d.date = ?1
+                     AND u.notification_time = ?2
+                     ORDER BY u.telegram_chat_id",
+                )?;
+                let rows = statement.query_map(params![date, time], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?;
+                rows.collect()
+            })
+            .await?;
+
+        Ok(rows_to_users(rows))
+    }
+
+    async fn claim_notifications(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+        trash_types: Vec<String>,
+    ) -> Result<Vec<String>> {
+        self.with_connection(move |connection| {
+            let mut claimed = Vec::new();
+            for trash_type in trash_types {
+                if connection.execute(
+                    "INSERT INTO sent_notifications (telegram_chat_id, date, trash_type)
+                     VALUES (?1, ?2, ?3)
+                     ON CONFLICT (telegram_chat_id, date, trash_type) DO NOTHING",
+                    params![telegram_chat_id, date, trash_type],
+                )? == 1
+                {
+                    claimed.push(trash_type);
+                }
+            }
+            Ok(claimed)
+        })
+        .await
+    }
+
+    async fn release_notifications(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+        trash_types: Vec<String>,
+    ) -> Result<()> {
+        self.with_connection(move |connection| {
+            for trash_type in trash_types {
+                connection.execute(
+                    "DELETE FROM sent_notifications
+                     WHERE telegram_chat_id = ?1 AND date = ?2 AND trash_type = ?3",
+                    params![telegram_chat_id, date, trash_type],
+                )?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn prune_sent_notifications(&self, before: NaiveDate) -> Result<usize> {
+        self.with_connection(move |connection| {
+            Ok(connection.execute(
+                "DELETE FROM sent_notifications WHERE date < ?1",
+                params![before],
+            )? + connection.execute(
+                "DELETE FROM failed_notifications WHERE date < ?1",
+                params![before],
+            )?)
+        })
+        .await
+    }
+
+    async fn record_failed_notification(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+    ) -> Result<()> {
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO failed_notifications (telegram_chat_id, date) VALUES (?1, ?2)",
+                params![telegram_chat_id, date],
+            )
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_reminder_counts(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<ReminderCounts> {
+        let from = from.format(TIMESTAMP_FORMAT).to_string();
+        let to = to.format(TIMESTAMP_FORMAT).to_string();
+
+        self.with_connection(move |connection| {
+            Ok(ReminderCounts {
+                sent: connection.query_row(
+                    "SELECT COUNT(*) FROM (
+                         SELECT DISTINCT telegram_chat_id, date FROM sent_notifications
+                         WHERE created_at >= ?1 AND created_at < ?2
+                     )",
+                    params![from, to],
+                    |row| row.get(0),
+                )?,
+                failed: connection.query_row(
+                    "SELECT COUNT(*) FROM failed_notifications
+                     WHERE created_at >= ?1 AND created_at < ?2",
+                    params![from, to],
+                    |row| row.get(0),
+                )?,
+            })
+        })
+        .await
+    }
+
+    async fn set_notification_time(
+        &self,
+        telegram_chat_id: i64,
+        notification_time: NaiveTime,
+    ) -> Result<NaiveTime> {
+        let affected_rows = self
+            .with_connection(move |connection| {
+                connection.execute(
+                    "UPDATE users SET notification_time = ?2 WHERE telegram_chat_id = ?1",
+                    params![telegram_chat_id, notification_time],
+                )
+            })
+            .await?;
+
+        if affected_rows == 1 {
+            Ok(notification_time)
+        } else {
+            Err(StorageError::NotFound("user").into())
+        }
+    }
+
+    async fn get_language(&self, telegram_chat_id: i64) -> Result<Option<String>> {
+        let language = self
+            .with_connection(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT language FROM users WHERE telegram_chat_id = ?1",
+                        params![telegram_chat_id],
+                        |row| row.get::<_, Option<String>>(0),
+                    )
+                    .optional()
+            })
+            .await?;
+
+        Ok(language.flatten())
+    }
+
+    async fn set_language(&self, telegram_chat_id: i64, language: String) -> Result<()> {
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO users (telegram_chat_id, language) VALUES (?1, ?2)
+                 ON CONFLICT (telegram_chat_id) DO UPDATE SET language = excluded.language",
+                params![telegram_chat_id, language],
+            )
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_trash_types(&self) -> Result<Vec<TrashTypeInfo>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT name, emoji, colour, description FROM trash_types ORDER BY name",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok(TrashTypeInfo {
+                    name: row.get(0)?,
+                    emoji: row.get(1)?,
+                    colour: row.get(2)?,
+                    description: row.get(3)?,
+                })
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_unsubscribed_trash_types(&self, telegram_chat_id: i64) -> Result<Vec<String>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT t.name FROM unsubscribed_trash_types u
+                 JOIN trash_types t ON t.id = u.trash_type
+                 WHERE u.telegram_chat_id = ?1",
+            )?;
+            let rows = statement.query_map(params![telegram_chat_id], |row| row.get(0))?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn set_trash_type_subscription(
+        &self,
+        telegram_chat_id: i64,
+        trash_type: String,
+        subscribed: bool,
+    ) -> Result<bool> {
+        let known = self
+            .with_connection(move |connection| {
+                let trash_type_id: Option<i64> = connection
+                    .query_row(
+                        "SELECT id FROM trash_types WHERE name = ?1",
+                        params![trash_type],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+
+                match (trash_type_id, subscribed) {
+                    (Some(trash_type_id), true) => connection.execute(
+                        "DELETE FROM unsubscribed_trash_types
+                         WHERE telegram_chat_id = ?1 AND trash_type = ?2",
+                        params![telegram_chat_id, trash_type_id],
+                    )?,
+                    (Some(trash_type_id), false) => connection.execute(
+                        "INSERT INTO unsubscribed_trash_types (telegram_chat_id, trash_type)
+                         VALUES (?1, ?2)
+                         ON CONFLICT (telegram_chat_id, trash_type) DO NOTHING",
+                        params![telegram_chat_id, trash_type_id],
+                    )?,
+                    (None, _) => 0,
+                };
+
+                Ok(trash_type_id.is_some())
+            })
+            .await?;
+
+        if !known {
+            return Err(StorageError::NotFound("trash type").into());
+        }
+
+        Ok(subscribed)
+    }
+
+    async fn get_statistics(&self) -> Result<Statistics> {
+        self.with_connection(|connection| {
+            let (users, notifications_enabled) = connection.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(enabled_notifications), 0) FROM users u
+                 WHERE EXISTS (SELECT 1 FROM addresses a WHERE a.telegram_chat_id = u.telegram_chat_id)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            let mut statement = connection.prepare(
+                "SELECT s.name, COUNT(DISTINCT a.telegram_chat_id) FROM addresses a
+                 JOIN streets s ON s.id = a.street
+                 GROUP BY s.id
+                 ORDER BY 2 DESC, s.name",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    sentence_to_uppercase(&row.get::<_, String>(0)?),
+                    row.get(1)?,
+                ))
+            })?;
+
+            Ok(Statistics {
+                users,
+                notifications_enabled,
+                users_per_street: rows.collect::<rusqlite::Result<_>>()?,
+            })
+        })
+        .await
+    }
+
+    async fn get_registered_addresses(&self) -> Result<Vec<Address>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT a.id, a.telegram_chat_id, a.name, s.karlsruhe_id, a.house_number
+                 FROM addresses a JOIN streets s ON s.id = a.street",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok(Address {
+                    id: row.get(0)?,
+                    telegram_chat_id: row.get(1)?,
+                    name: row.get(2)?,
+                    karlsruhe_id: row.get(3)?,
+                    house_number: row.get(4)?,
+                })
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn upsert_streets(&self, streets: Vec<(i64, String)>) -> Result<usize> {
+        self.with_connection(move |connection| {
+            let mut stored = 0;
+            for (karlsruhe_id, name) in streets {
+                stored += connection.execute(
+                    "INSERT INTO streets (karlsruhe_id, name) VALUES (?1, ?2)
+                     ON CONFLICT (karlsruhe_id)
+                     DO UPDATE SET name = excluded.name, updated_at = CURRENT_TIMESTAMP",
+                    params![karlsruhe_id, name],
+                )?;
+            }
+            Ok(stored)
+        })
+        .await
+    }
+
+    async fn upsert_dates(&self, address: &Address, dates: Vec<TrashDate>) -> Result<usize> {
+        let (user_id, address_id) = (address.telegram_chat_id, address.id);
+
+        self.with_connection(move |connection| {
+            let mut inserted = 0;
+            for date in dates {
+                inserted += connection.execute(
+                    "INSERT INTO dates (date, trash_type, user_id, address_id)
+                     SELECT ?1, id, ?3, ?4 FROM trash_types WHERE name = ?2
+                     ON CONFLICT (trash_type, date, address_id) DO NOTHING",
+                    params![date.date, date.name, user_id, address_id],
+                )?;
+            }
+            Ok(inserted)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAT: i64 = 1111111;
+
+    async fn storage_with_user() -> SqliteStorage {
+        let storage = SqliteStorage::open(":memory:").unwrap();
+        storage
+            .set_language(CHAT, String::from("de"))
+            .await
+            .unwrap();
+        storage
+    }
+
+    #[tokio::test]
+    async fn maps_sqlite_errors() {
+        let storage = storage_with_user().await;
+
+        let missing = storage
+            .with_connection(|connection| {
+                connection.query_row("SELECT 1 WHERE 0", [], |row| row.get::<_, i64>(0))
+            })
+            .await
+            .unwrap_err();
+        let invalid = storage
+            .with_connection(|connection| connection.execute("SELECT FROM", []))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            StorageError::of(&missing),
+            Some(StorageError::NotFound(_))
+        ));
+        assert!(matches!(
+            StorageError::of(&invalid),
+            Some(StorageError::Sqlite(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_notification_change_of_unregistered_chat() {
+        let storage = SqliteStorage::open(":memory:").unwrap();
+
+        let error = storage.set_notification(CHAT, true).await.unwrap_err();
+
+        assert!(matches!(
+            StorageError::of(&error),
+            Some(StorageError::NotFound("user"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn enables_notifications_disabled_because_unreachable() {
+        let storage = storage_with_user().await;
+
+        assert!(storage.set_unreachable(CHAT, true).await.unwrap());
+        assert!(!storage.get_notification_status(CHAT).await.unwrap());
+        assert!(storage.set_unreachable(CHAT, false).await.unwrap());
+        assert!(storage.get_notification_status(CHAT).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn keeps_notifications_disabled_by_user() {
+        let storage = storage_with_user().await;
+        storage.set_notification(CHAT, false).await.unwrap();
+
+        assert!(!storage.set_unreachable(CHAT, true).await.unwrap());
+        assert!(!storage.set_unreachable(CHAT, false).await.unwrap());
+        assert!(!storage.get_notification_status(CHAT).await.unwrap());
+    }
+}
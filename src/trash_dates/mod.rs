@@ -1,14 +1,20 @@
 use std::env;
 use std::fmt::{Debug, Formatter};
 
-use anyhow::{Error, Result};
-use chrono::{NaiveDate, Utc};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use graphql_client::{GraphQLQuery, Response};
 use reqwest::Client;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::bot_logic::strings::Strings;
+use crate::metrics;
+use crate::storage::{Storage, StorageError};
+use crate::trash_types::{TrashTypeInfo, REGISTRY};
+
 static HASURA_HEADER: &str = "x-hasura-admin-secret";
 
 #[derive(Debug)]
@@ -16,6 +22,7 @@ pub struct InvalidDateError;
 
 type Date = chrono::NaiveDate;
 type Timestamptz = chrono::DateTime<Utc>;
+type Time = chrono::NaiveTime;
 
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub enum TrashType {
@@ -24,6 +31,8 @@ pub enum TrashType {
     Paper,
     Miscellaneous,
     Debug,
+    /// Any other type in the `trash_types` table, e.g. "Glas".
+    Unknown(String),
 }
 
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -59,12 +68,25 @@ impl From<user_data::UserDataUsersByPk> for HashMap<String, String> {
             aud.enabled_notifications.to_string(),
         );
         map.insert(
-            "house_number".to_string(),
-            aud.house_number.unwrap_or_default(),
+            "notification_time".to_string(),
+            aud.notification_time.format("%H:%M").to_string(),
         );
-        map.insert("street".to_string(), aud.street.to_string());
+        if let Some(language) = aud.language {
+            map.insert("language".to_string(), language);
+        }
         map.insert("chat_id".to_string(), aud.telegram_chat_id.to_string());
-        map.insert("street".to_string(), aud.street_by_street.name);
+        for address in aud.addresses {
+            map.insert(
+                format!("address ({})", address.name),
+                format!(
+                    "{} {}",
+                    sentence_to_uppercase(&address.street_by_street.name),
+                    address.house_number.unwrap_or_default()
+                )
+                .trim_end()
+                .to_string(),
+            );
+        }
 
         map
     }
@@ -82,29 +104,38 @@ pub struct SearchStreet;
 #[derive(GraphQLQuery, Debug)]
 #[graphql(
     schema_path = "graphql/schema.graphql",
-    query_path = "graphql/tomorrow_for_all.graphql",
+    query_path = "graphql/upsert_street.graphql",
     response_derives = "Debug",
     normalization = "rust"
 )]
-pub struct TomorrowForAll;
+pub struct UpsertStreet;
 
 #[derive(GraphQLQuery, Debug)]
 #[graphql(
     schema_path = "graphql/schema.graphql",
-    query_path = "graphql/users.graphql",
+    query_path = "graphql/add_address.graphql",
     response_derives = "Debug",
     normalization = "rust"
 )]
-pub struct ActiveUsers;
+pub struct AddAddress;
 
 #[derive(GraphQLQuery, Debug)]
 #[graphql(
     schema_path = "graphql/schema.graphql",
-    query_path = "graphql/add_user.graphql",
+    query_path = "graphql/addresses.graphql",
     response_derives = "Debug",
     normalization = "rust"
 )]
-pub struct AddUser;
+pub struct Addresses;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/delete_address.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct DeleteAddress;
 
 #[derive(GraphQLQuery, Debug)]
 #[graphql(
@@ -115,6 +146,24 @@ pub struct AddUser;
 )]
 pub struct SetNotification;
 
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/disable_unreachable.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct DisableUnreachable;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/enable_reachable.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct EnableReachable;
+
 #[derive(GraphQLQuery, Debug)]
 #[graphql(
     schema_path = "graphql/schema.graphql",
@@ -133,6 +182,177 @@ pub struct DeleteUser;
 )]
 pub struct NotificationStatus;
 
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/registered_addresses.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct RegisteredAddresses;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/trash_types.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct TrashTypes;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/chat_language.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct ChatLanguage;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/set_language.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct SetLanguage;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/unsubscribed_trash_types.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct UnsubscribedTrashTypes;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/unsubscribe_trash_type.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct UnsubscribeTrashType;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/subscribe_trash_type.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct SubscribeTrashType;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/upsert_date.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct UpsertDate;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/upcoming_for_user.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct UpcomingForUser;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/dates_between.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct DatesBetween;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/next_per_type.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct NextPerType;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/users_due.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct UsersDue;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/set_notification_time.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct SetNotificationTime;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/user_statistics.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct UserStatistics;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/claim_notification.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct ClaimNotification;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/release_notifications.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct ReleaseNotifications;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/prune_sent_notifications.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct PruneSentNotifications;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/record_failed_notification.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct RecordFailedNotification;
+
+#[derive(GraphQLQuery, Debug)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/count_reminders.graphql",
+    response_derives = "Debug",
+    normalization = "rust"
+)]
+pub struct CountReminders;
+
 #[derive(Debug, Clone)]
 pub struct RequestPerformer {
     secret: String,
@@ -145,6 +365,34 @@ pub struct TrashDate {
     pub date: NaiveDate,
     pub trash_type: TrashType,
     pub name: String,
+    /// Name of the address profile the date belongs to, e.g. "Zuhause".
+    pub address: Option<String>,
+}
+
+/// A registered user's address, as needed to look up the city's waste calendar.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Address {
+    pub id: i64,
+    pub telegram_chat_id: i64,
+    pub name: String,
+    pub karlsruhe_id: i64,
+    pub house_number: Option<String>,
+}
+
+/// Numbers about the registered users, shown to the operators.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    pub users: i64,
+    pub notifications_enabled: i64,
+    /// Street names with the number of chats that saved an address there, most users first.
+    pub users_per_street: Vec<(String, i64)>,
+}
+
+/// Reminder messages sent by the scheduler and messages that could not be sent.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ReminderCounts {
+    pub sent: i64,
+    pub failed: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -153,6 +401,14 @@ pub struct Street {
     pub id: i64,
 }
 
+/// The current date in Karlsruhe.
+pub fn today() -> NaiveDate {
+    Utc::now()
+        .with_timezone(&chrono_tz::Europe::Berlin)
+        .date()
+        .naive_local()
+}
+
 fn word_to_uppercase(string: &str) -> String {
     let mut c = string.chars();
     match c.next() {
@@ -161,7 +417,7 @@ fn word_to_uppercase(string: &str) -> String {
     }
 }
 
-fn sentence_to_uppercase(s: &str) -> String {
+pub(crate) fn sentence_to_uppercase(s: &str) -> String {
     s.split_whitespace()
         .map(word_to_uppercase)
         .collect::<Vec<String>>()
@@ -177,17 +433,56 @@ impl From<search_street::SearchStreetSearchStreets> for Street {
     }
 }
 
-impl From<active_users::ActiveUsersUsers> for User {
-    fn from(au: active_users::ActiveUsersUsers) -> Self {
-        User {
-            client_id: au.telegram_chat_id,
-            dates: Vec::new(),
+impl From<user_statistics::ResponseData> for Statistics {
+    fn from(us: user_statistics::ResponseData) -> Self {
+        let mut users_per_street: Vec<(String, i64)> = us
+            .streets
+            .into_iter()
+            .map(|street| {
+                (
+                    sentence_to_uppercase(&street.name),
+                    street
+                        .addresses_aggregate
+                        .aggregate
+                        .map(|aggregate| aggregate.count)
+                        .unwrap_or_default(),
+                )
+            })
+            .collect();
+        // Same order as the SQLite storage: most users first, then by name.
+        users_per_street
+            .sort_by(|(name_a, a), (name_b, b)| b.cmp(a).then_with(|| name_a.cmp(name_b)));
+
+        Statistics {
+            users: us
+                .users_aggregate
+                .aggregate
+                .map(|aggregate| aggregate.count)
+                .unwrap_or_default(),
+            notifications_enabled: us
+                .notifications
+                .aggregate
+                .map(|aggregate| aggregate.count)
+                .unwrap_or_default(),
+            users_per_street,
+        }
+    }
+}
+
+impl From<registered_addresses::RegisteredAddressesAddresses> for Address {
+    fn from(ra: registered_addresses::RegisteredAddressesAddresses) -> Self {
+        Address {
+            id: ra.id,
+            telegram_chat_id: ra.telegram_chat_id,
+            name: ra.name,
+            karlsruhe_id: ra.street_by_street.karlsruhe_id,
+            house_number: ra.house_number,
         }
     }
 }
 
-impl From<tomorrow_for_all::TomorrowForAllUsers> for User {
-    fn from(au: tomorrow_for_all::TomorrowForAllUsers) -> Self {
+impl From<users_due::UsersDueUsers> for User {
+    fn from(au: users_due::UsersDueUsers) -> Self {
         User {
             client_id: au.telegram_chat_id,
             dates: au.dates.into_iter().map(TrashDate::from).collect(),
@@ -195,12 +490,13 @@ impl From<tomorrow_for_all::TomorrowForAllUsers> for User {
     }
 }
 
-impl From<tomorrow_for_all::TomorrowForAllUsersDates> for TrashDate {
-    fn from(tat: tomorrow_for_all::TomorrowForAllUsersDates) -> Self {
+impl From<users_due::UsersDueUsersDates> for TrashDate {
+    fn from(tat: users_due::UsersDueUsersDates) -> Self {
         TrashDate {
             name: String::from(&tat.trash_type_by_trash_type.name[..]),
             date: tat.date,
             trash_type: TrashType::from(&tat.trash_type_by_trash_type.name[..]),
+            address: Some(tat.address.name),
         }
     }
 }
@@ -213,7 +509,7 @@ impl From<&str> for TrashType {
             "Papier" => TrashType::Paper,
             "Restmüll" => TrashType::Miscellaneous,
             "Debug" => TrashType::Debug,
-            _ => panic!("Could not find the selected type of trash: {}", string),
+            _ => TrashType::Unknown(String::from(string)),
         }
     }
 }
@@ -224,13 +520,73 @@ impl From<tomorrow_for_user::TomorrowForUserDates> for TrashDate {
             name: String::from(&tat.trash_type_by_trash_type.name[..]),
             date: tat.date,
             trash_type: TrashType::from(&tat.trash_type_by_trash_type.name[..]),
+            address: Some(tat.address.name),
         }
     }
 }
 
+impl From<upcoming_for_user::UpcomingForUserDates> for TrashDate {
+    fn from(tat: upcoming_for_user::UpcomingForUserDates) -> Self {
+        TrashDate {
+            name: String::from(&tat.trash_type_by_trash_type.name[..]),
+            date: tat.date,
+            trash_type: TrashType::from(&tat.trash_type_by_trash_type.name[..]),
+            address: Some(tat.address.name),
+        }
+    }
+}
+
+impl From<dates_between::DatesBetweenDates> for TrashDate {
+    fn from(tat: dates_between::DatesBetweenDates) -> Self {
+        TrashDate {
+            name: String::from(&tat.trash_type_by_trash_type.name[..]),
+            date: tat.date,
+            trash_type: TrashType::from(&tat.trash_type_by_trash_type.name[..]),
+            address: Some(tat.address.name),
+        }
+    }
+}
+
+impl TrashDate {
+    /// The translated trash type with its emoji, followed by the address profile if known, e.g.
+    /// "📰 Papier (Eltern)".
+    pub fn label(&self, text: &Strings) -> String {
+        let trash_type = REGISTRY.get(&self.name).label(text);
+        match &self.address {
+            Some(address) => format!("{} ({})", trash_type, address),
+            None => trash_type,
+        }
+    }
+
+    /// The trash type with its bin colour and description, followed by the address profile if
+    /// known, e.g. "📰 Papier (blau): Zeitungen und Kartons – Eltern".
+    pub fn details(&self, text: &Strings) -> String {
+        let trash_type = REGISTRY.get(&self.name).details(text);
+        match &self.address {
+            Some(address) => format!("{} – {}", trash_type, address),
+            None => trash_type,
+        }
+    }
+}
+
+/// For logs, messages use [`TrashDate::label`].
 impl std::fmt::Display for TrashDate {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}: {}", self.name, self.date)
+        match &self.address {
+            Some(address) => write!(f, "{} ({}): {}", self.name, address, self.date),
+            None => write!(f, "{}: {}", self.name, self.date),
+        }
+    }
+}
+
+impl From<next_per_type::NextPerTypeDates> for TrashDate {
+    fn from(tat: next_per_type::NextPerTypeDates) -> Self {
+        TrashDate {
+            name: String::from(&tat.trash_type_by_trash_type.name[..]),
+            date: tat.date,
+            trash_type: TrashType::from(&tat.trash_type_by_trash_type.name[..]),
+            address: Some(tat.address.name),
+        }
     }
 }
 
@@ -258,8 +614,61 @@ impl RequestPerformer {
         )
     }
 
-    #[allow(dead_code)]
-    pub async fn get_tomorrows_trash(&self, user_id: i64) -> Result<Vec<TrashDate>> {
+    fn log_errors<T: Debug>(&self, response: Response<T>) -> Result<T, StorageError> {
+        log::info!("graphql response: {:?}", response);
+
+        match response.errors {
+            Some(errors) if !errors.is_empty() => Err(StorageError::GraphQl(errors)),
+            _ => response
+                .data
+                .ok_or_else(|| StorageError::Decode(String::from("no data in query result"))),
+        }
+    }
+
+    async fn send_request<T: Serialize + ?Sized, R: DeserializeOwned + Debug>(
+        &self,
+        json: &T,
+    ) -> Result<R, StorageError> {
+        let _timer = metrics::GRAPHQL_REQUEST_DURATION.start_timer();
+
+        let result = async {
+            let response = self
+                .client
+                .post(&self.endpoint)
+                .header(HASURA_HEADER, &self.secret)
+                .json(json)
+                .send()
+                .await
+                .map_err(StorageError::Network)?;
+            if !response.status().is_success() {
+                return Err(StorageError::Status(response.status()));
+            }
+
+            let response = response
+                .json::<graphql_client::Response<R>>()
+                .await
+                .map_err(|e| {
+                    if e.is_decode() {
+                        StorageError::Decode(e.to_string())
+                    } else {
+                        StorageError::Network(e)
+                    }
+                })?;
+            self.log_errors(response)
+        }
+        .await;
+
+        if let Err(e) = &result {
+            log::error!("GraphQL request failed: {}", e);
+            metrics::GRAPHQL_ERRORS.inc();
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Storage for RequestPerformer {
+    async fn get_tomorrows_trash(&self, user_id: i64) -> Result<Vec<TrashDate>> {
         let request_body = TomorrowForUser::build_query(tomorrow_for_user::Variables { user_id });
         let response_data: tomorrow_for_user::ResponseData =
             self.send_request(&request_body).await?;
@@ -271,36 +680,70 @@ impl RequestPerformer {
             .collect())
     }
 
-    fn log_errors<T: Debug>(&self, response: Response<T>) -> Result<T> {
-        log::info!("graphql response: {:?}", response);
+    async fn get_upcoming_trash(&self, user_id: i64) -> Result<Vec<TrashDate>> {
+        let request_body = UpcomingForUser::build_query(upcoming_for_user::Variables {
+            user_id,
+            from: today(),
+        });
+        let response_data: upcoming_for_user::ResponseData =
+            self.send_request(&request_body).await?;
 
-        if let Some(errors) = &response.errors {
-            log::error!("Something failed!");
+        Ok(response_data
+            .dates
+            .into_iter()
+            .map(TrashDate::from)
+            .collect())
+    }
 
-            for error in errors {
-                log::error!("{:?}", error);
-            }
+    async fn get_trash_between(
+        &self,
+        user_id: i64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<TrashDate>> {
+        let request_body =
+            DatesBetween::build_query(dates_between::Variables { user_id, from, to });
+        let response_data: dates_between::ResponseData = self.send_request(&request_body).await?;
 
-            Err(Error::msg(
-                "at least one error occurred while querying graphql",
-            ))
-        } else {
-            response
-                .data
-                .ok_or_else(|| Error::msg("no data in query result"))
-        }
+        Ok(response_data
+            .dates
+            .into_iter()
+            .map(TrashDate::from)
+            .collect())
     }
 
-    pub async fn get_street_id(&self, street_name: String) -> Result<i64> {
+    async fn get_next_per_type(&self, user_id: i64) -> Result<Vec<TrashDate>> {
+        let request_body = NextPerType::build_query(next_per_type::Variables {
+            user_id,
+            from: today(),
+        });
+        let response_data: next_per_type::ResponseData = self.send_request(&request_body).await?;
+
+        let mut dates: Vec<TrashDate> = response_data
+            .dates
+            .into_iter()
+            .map(TrashDate::from)
+            .collect();
+        dates.sort_by_key(|date| date.date);
+
+        Ok(dates)
+    }
+
+    async fn get_street_id(&self, street_name: String) -> Result<i64> {
         let response_body = SearchStreet::build_query(search_street::Variables {
             limit: Some(1i64),
             name: Some(street_name),
         });
         let result: search_street::ResponseData = self.send_request(&response_body).await?;
-        Ok(result.search_streets.into_iter().next().unwrap().id)
+        Ok(result
+            .search_streets
+            .into_iter()
+            .next()
+            .ok_or(StorageError::NotFound("street"))?
+            .id)
     }
 
-    pub async fn get_notification_status(&self, telegram_chat_id: i64) -> Result<bool> {
+    async fn get_notification_status(&self, telegram_chat_id: i64) -> Result<bool> {
         let response_body = NotificationStatus::build_query(notification_status::Variables {
             user_id: telegram_chat_id,
         });
@@ -308,22 +751,33 @@ impl RequestPerformer {
         let result: notification_status::ResponseData = self.send_request(&response_body).await?;
         Ok(result
             .users_by_pk
-            .ok_or_else(|| Error::msg("user not found"))?
+            .ok_or(StorageError::NotFound("user"))?
             .enabled_notifications)
     }
 
-    pub async fn get_my_user_data(&self, telegram_chat_id: i64) -> Result<HashMap<String, String>> {
+    async fn get_my_user_data(&self, telegram_chat_id: i64) -> Result<HashMap<String, String>> {
         let response_body = UserData::build_query(user_data::Variables { telegram_chat_id });
 
         let result = self
             .send_request::<graphql_client::QueryBody<user_data::Variables>, user_data::ResponseData>(&response_body)
-            .await?
-            .users_by_pk
-            .ok_or_else(|| Error::msg("could not find user"))?;
-        Ok(HashMap::from(result))
+            .await?;
+
+        let mut map = HashMap::from(result.users_by_pk.ok_or(StorageError::NotFound("user"))?);
+        if !result.unsubscribed_trash_types.is_empty() {
+            map.insert(
+                "unsubscribed_trash_types".to_string(),
+                result
+                    .unsubscribed_trash_types
+                    .into_iter()
+                    .map(|unsubscribed| unsubscribed.trash_type_by_trash_type.name)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            );
+        }
+        Ok(map)
     }
 
-    pub async fn search_similar_streets(&self, street_name: String) -> Result<Vec<Street>> {
+    async fn search_similar_streets(&self, street_name: String) -> Result<Vec<Street>> {
         let response_body = SearchStreet::build_query(search_street::Variables {
             limit: Some(5i64),
             name: Some(street_name),
@@ -336,83 +790,437 @@ impl RequestPerformer {
             .collect())
     }
 
-    pub async fn remove_user_data(&self, telegram_chat_id: i64) -> Result<bool> {
+    async fn remove_user_data(&self, telegram_chat_id: i64) -> Result<bool> {
         let response_body = DeleteUser::build_query(delete_user::Variables {
             telegram_chat_id: Some(telegram_chat_id),
         });
         let result: delete_user::ResponseData = self.send_request(&response_body).await?;
         Ok(result
             .delete_users
-            .ok_or_else(|| Error::msg("user not found"))?
+            .ok_or(StorageError::NotFound("user"))?
             .affected_rows
             == 1)
     }
 
-    pub async fn add_user(
+    async fn add_address(
         &self,
         telegram_chat_id: i64,
-        street: Option<i64>,
+        name: String,
+        street: i64,
         house_number: Option<String>,
-    ) -> Result<add_user::ResponseData> {
-        let response_body = AddUser::build_query(add_user::Variables {
+    ) -> Result<()> {
+        let response_body = AddAddress::build_query(add_address::Variables {
             telegram_chat_id,
+            name,
             street,
             house_number,
         });
 
-        self.send_request::<graphql_client::QueryBody<add_user::Variables>, add_user::ResponseData>(
+        self.send_request::<graphql_client::QueryBody<add_address::Variables>, add_address::ResponseData>(
             &response_body,
         )
-        .await
+        .await?;
+        Ok(())
     }
 
-    async fn send_request<T: Serialize + ?Sized, R: DeserializeOwned + Debug>(
-        &self,
-        json: &T,
-    ) -> Result<R> {
-        self.log_errors(
-            self.client
-                .post(&self.endpoint)
-                .header(HASURA_HEADER, &self.secret)
-                .json(json)
-                .send()
-                .await
-                .unwrap()
-                .json::<graphql_client::Response<R>>()
-                .await
-                .unwrap(),
-        )
+    async fn get_addresses(&self, telegram_chat_id: i64) -> Result<Vec<String>> {
+        let response_body = Addresses::build_query(addresses::Variables { telegram_chat_id });
+        let result: addresses::ResponseData = self.send_request(&response_body).await?;
+
+        Ok(result
+            .addresses
+            .into_iter()
+            .map(|address| address.name)
+            .collect())
     }
 
-    pub async fn set_notification(
-        &self,
-        telegram_chat_id: i64,
-        notifications: bool,
-    ) -> Result<bool> {
+    async fn remove_address(&self, telegram_chat_id: i64, name: String) -> Result<bool> {
+        let response_body = DeleteAddress::build_query(delete_address::Variables {
+            telegram_chat_id,
+            name,
+        });
+        let result: delete_address::ResponseData = self.send_request(&response_body).await?;
+        Ok(result
+            .delete_addresses
+            .ok_or(StorageError::NotFound("address"))?
+            .affected_rows
+            == 1)
+    }
+
+    async fn set_notification(&self, telegram_chat_id: i64, notifications: bool) -> Result<bool> {
         let response_body = SetNotification::build_query(set_notification::Variables {
             telegram_chat_id,
             enabled_notifications: notifications,
         });
 
-        self.send_request::<graphql_client::QueryBody<set_notification::Variables>, set_notification::ResponseData>(
-            &response_body,
-        ).await?;
+        let response_data: set_notification::ResponseData =
+            self.send_request(&response_body).await?;
+        response_data
+            .update_users_by_pk
+            .ok_or(StorageError::NotFound("user"))?;
+
         Ok(notifications)
     }
 
-    #[allow(dead_code)]
-    pub async fn get_active_users(&self) -> Result<Vec<User>> {
-        let response_body = ActiveUsers::build_query(active_users::Variables {});
-        let response_data: active_users::ResponseData = self.send_request(&response_body).await?;
+    async fn set_unreachable(&self, telegram_chat_id: i64, unreachable: bool) -> Result<bool> {
+        let affected_rows = if unreachable {
+            let request_body = DisableUnreachable::build_query(disable_unreachable::Variables {
+                telegram_chat_id,
+            });
+            let response_data: disable_unreachable::ResponseData =
+                self.send_request(&request_body).await?;
+            response_data
+                .update_users
+                .map(|updated| updated.affected_rows)
+        } else {
+            let request_body =
+                EnableReachable::build_query(enable_reachable::Variables { telegram_chat_id });
+            let response_data: enable_reachable::ResponseData =
+                self.send_request(&request_body).await?;
+            response_data
+                .update_users
+                .map(|updated| updated.affected_rows)
+        };
+
+        Ok(affected_rows.unwrap_or_default() > 0)
+    }
+
+    async fn get_users_due(&self, date: NaiveDate, time: NaiveTime) -> Result<Vec<User>> {
+        let request_body = UsersDue::build_query(users_due::Variables { date, time });
+        let response_data: users_due::ResponseData = self.send_request(&request_body).await?;
 
         Ok(response_data.users.into_iter().map(User::from).collect())
     }
 
-    pub async fn get_active_users_tomorrow(&self) -> Result<Vec<User>> {
-        let request_body = TomorrowForAll::build_query(tomorrow_for_all::Variables {});
-        let response_data: tomorrow_for_all::ResponseData =
+    async fn claim_notifications(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+        trash_types: Vec<String>,
+    ) -> Result<Vec<String>> {
+        let mut claimed = Vec::new();
+        for trash_type in trash_types {
+            let request_body = ClaimNotification::build_query(claim_notification::Variables {
+                telegram_chat_id,
+                date,
+                trash_type,
+            });
+            let response_data: claim_notification::ResponseData =
+                self.send_request(&request_body).await?;
+
+            if let Some(sent) = response_data.insert_sent_notifications_one {
+                claimed.push(sent.trash_type);
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    async fn release_notifications(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+        trash_types: Vec<String>,
+    ) -> Result<()> {
+        let request_body = ReleaseNotifications::build_query(release_notifications::Variables {
+            telegram_chat_id,
+            date,
+            trash_types,
+        });
+
+        self.send_request::<graphql_client::QueryBody<release_notifications::Variables>, release_notifications::ResponseData>(
+            &request_body,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn prune_sent_notifications(&self, before: NaiveDate) -> Result<usize> {
+        let request_body =
+            PruneSentNotifications::build_query(prune_sent_notifications::Variables { before });
+        let response_data: prune_sent_notifications::ResponseData =
             self.send_request(&request_body).await?;
 
-        Ok(response_data.users.into_iter().map(User::from).collect())
+        Ok(response_data
+            .delete_sent_notifications
+            .map(|deleted| deleted.affected_rows as usize)
+            .unwrap_or_default()
+            + response_data
+                .delete_failed_notifications
+                .map(|deleted| deleted.affected_rows as usize)
+                .unwrap_or_default())
+    }
+
+    async fn record_failed_notification(
+        &self,
+        telegram_chat_id: i64,
+        date: NaiveDate,
+    ) -> Result<()> {
+        let request_body =
+            RecordFailedNotification::build_query(record_failed_notification::Variables {
+                telegram_chat_id,
+                date,
+            });
+
+        self.send_request::<graphql_client::QueryBody<record_failed_notification::Variables>, record_failed_notification::ResponseData>(
+            &request_body,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_reminder_counts(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<ReminderCounts> {
+        let request_body = CountReminders::build_query(count_reminders::Variables { from, to });
+        let response_data: count_reminders::ResponseData = self.send_request(&request_body).await?;
+
+        Ok(ReminderCounts {
+            sent: response_data
+                .sent
+                .aggregate
+                .map(|aggregate| aggregate.count)
+                .unwrap_or_default(),
+            failed: response_data
+                .failed
+                .aggregate
+                .map(|aggregate| aggregate.count)
+                .unwrap_or_default(),
+        })
+    }
+
+    async fn set_notification_time(
+        &self,
+        telegram_chat_id: i64,
+        notification_time: NaiveTime,
+    ) -> Result<NaiveTime> {
+        let request_body = SetNotificationTime::build_query(set_notification_time::Variables {
+            telegram_chat_id,
+            notification_time,
+        });
+        let response_data: set_notification_time::ResponseData =
+            self.send_request(&request_body).await?;
+
+        Ok(response_data
+            .update_users_by_pk
+            .ok_or(StorageError::NotFound("user"))?
+            .notification_time)
+    }
+
+    async fn get_language(&self, telegram_chat_id: i64) -> Result<Option<String>> {
+        let request_body = ChatLanguage::build_query(chat_language::Variables { telegram_chat_id });
+        let response_data: chat_language::ResponseData = self.send_request(&request_body).await?;
+
+        Ok(response_data.users_by_pk.and_then(|user| user.language))
+    }
+
+    async fn set_language(&self, telegram_chat_id: i64, language: String) -> Result<()> {
+        let request_body = SetLanguage::build_query(set_language::Variables {
+            telegram_chat_id,
+            language,
+        });
+
+        self.send_request::<graphql_client::QueryBody<set_language::Variables>, set_language::ResponseData>(
+            &request_body,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_trash_types(&self) -> Result<Vec<TrashTypeInfo>> {
+        let request_body = TrashTypes::build_query(trash_types::Variables {});
+        let response_data: trash_types::ResponseData = self.send_request(&request_body).await?;
+
+        let mut trash_types: Vec<TrashTypeInfo> = response_data
+            .trash_types
+            .into_iter()
+            .map(|trash_type| TrashTypeInfo {
+                name: trash_type.name,
+                emoji: trash_type.emoji,
+                colour: trash_type.colour,
+                description: trash_type.description,
+            })
+            .collect();
+        trash_types.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(trash_types)
+    }
+
+    async fn get_unsubscribed_trash_types(&self, telegram_chat_id: i64) -> Result<Vec<String>> {
+        let request_body =
+            UnsubscribedTrashTypes::build_query(unsubscribed_trash_types::Variables {
+                telegram_chat_id,
+            });
+        let response_data: unsubscribed_trash_types::ResponseData =
+            self.send_request(&request_body).await?;
+
+        Ok(response_data
+            .unsubscribed_trash_types
+            .into_iter()
+            .map(|unsubscribed| unsubscribed.trash_type_by_trash_type.name)
+            .collect())
+    }
+
+    async fn set_trash_type_subscription(
+        &self,
+        telegram_chat_id: i64,
+        trash_type: String,
+        subscribed: bool,
+    ) -> Result<bool> {
+        if subscribed {
+            let request_body = SubscribeTrashType::build_query(subscribe_trash_type::Variables {
+                telegram_chat_id,
+                name: trash_type,
+            });
+            self.send_request::<graphql_client::QueryBody<subscribe_trash_type::Variables>, subscribe_trash_type::ResponseData>(
+                &request_body,
+            )
+            .await?;
+        } else {
+            let request_body = TrashTypes::build_query(trash_types::Variables {});
+            let trash_type = self
+                .send_request::<graphql_client::QueryBody<trash_types::Variables>, trash_types::ResponseData>(&request_body)
+                .await?
+                .trash_types
+                .into_iter()
+                .find(|known| known.name == trash_type)
+                .ok_or(StorageError::NotFound("trash type"))?
+                .id;
+
+            let request_body =
+                UnsubscribeTrashType::build_query(unsubscribe_trash_type::Variables {
+                    telegram_chat_id,
+                    trash_type,
+                });
+            self.send_request::<graphql_client::QueryBody<unsubscribe_trash_type::Variables>, unsubscribe_trash_type::ResponseData>(
+                &request_body,
+            )
+            .await?;
+        }
+
+        Ok(subscribed)
+    }
+
+    async fn get_statistics(&self) -> Result<Statistics> {
+        let request_body = UserStatistics::build_query(user_statistics::Variables {});
+        let response_data: user_statistics::ResponseData = self.send_request(&request_body).await?;
+
+        Ok(Statistics::from(response_data))
+    }
+
+    async fn get_registered_addresses(&self) -> Result<Vec<Address>> {
+        let request_body = RegisteredAddresses::build_query(registered_addresses::Variables {});
+        let response_data: registered_addresses::ResponseData =
+            self.send_request(&request_body).await?;
+
+        Ok(response_data
+            .addresses
+            .into_iter()
+            .map(Address::from)
+            .collect())
+    }
+
+    async fn upsert_streets(&self, streets: Vec<(i64, String)>) -> Result<usize> {
+        let mut stored = 0;
+        for (karlsruhe_id, name) in streets {
+            let request_body =
+                UpsertStreet::build_query(upsert_street::Variables { karlsruhe_id, name });
+            let response_data: upsert_street::ResponseData =
+                self.send_request(&request_body).await?;
+
+            if response_data.insert_streets_one.is_some() {
+                stored += 1;
+            }
+        }
+
+        Ok(stored)
+    }
+
+    async fn upsert_dates(&self, address: &Address, dates: Vec<TrashDate>) -> Result<usize> {
+        let request_body = TrashTypes::build_query(trash_types::Variables {});
+        let trash_types: HashMap<String, i64> = self
+            .send_request::<graphql_client::QueryBody<trash_types::Variables>, trash_types::ResponseData>(&request_body)
+            .await?
+            .trash_types
+            .into_iter()
+            .map(|trash_type| (trash_type.name, trash_type.id))
+            .collect();
+
+        let mut inserted = 0;
+        for date in dates {
+            let trash_type = match trash_types.get(&date.name) {
+                Some(trash_type) => *trash_type,
+                None => {
+                    log::warn!("Unknown trash type, skipping date: {}", date);
+                    continue;
+                }
+            };
+
+            let request_body = UpsertDate::build_query(upsert_date::Variables {
+                user_id: address.telegram_chat_id,
+                address_id: address.id,
+                date: date.date,
+                trash_type,
+            });
+            let response_data: upsert_date::ResponseData = self.send_request(&request_body).await?;
+
+            if response_data.insert_dates_one.is_some() {
+                inserted += 1;
+            }
+        }
+
+        Ok(inserted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn street(name: &str, users: i64) -> user_statistics::UserStatisticsStreets {
+        user_statistics::UserStatisticsStreets {
+            name: String::from(name),
+            addresses_aggregate: user_statistics::UserStatisticsStreetsAddressesAggregate {
+                aggregate: Some(
+                    user_statistics::UserStatisticsStreetsAddressesAggregateAggregate {
+                        count: users,
+                    },
+                ),
+            },
+        }
+    }
+
+    #[test]
+    fn sorts_users_per_street_by_count_then_name() {
+        let statistics = Statistics::from(user_statistics::ResponseData {
+            users_aggregate: user_statistics::UserStatisticsUsersAggregate {
+                aggregate: Some(user_statistics::UserStatisticsUsersAggregateAggregate {
+                    count: 5,
+                }),
+            },
+            notifications: user_statistics::UserStatisticsNotifications {
+                aggregate: Some(user_statistics::UserStatisticsNotificationsAggregate { count: 4 }),
+            },
+            streets: vec![
+                street("waldstraße", 1),
+                street("kaiserstraße", 2),
+                street("adlerstraße", 1),
+                street("zähringerstraße", 2),
+            ],
+        });
+
+        assert_eq!(statistics.users, 5);
+        assert_eq!(statistics.notifications_enabled, 4);
+        assert_eq!(
+            statistics.users_per_street,
+            vec![
+                (String::from("Kaiserstraße"), 2),
+                (String::from("Zähringerstraße"), 2),
+                (String::from("Adlerstraße"), 1),
+                (String::from("Waldstraße"), 1),
+            ]
+        );
     }
 }
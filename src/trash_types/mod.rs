@@ -0,0 +1,257 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+
+use crate::bot_logic::strings::Strings;
+use crate::storage::Storage;
+
+/// How often the trash types are reloaded from the storage.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Shown for trash types without an emoji.
+static DEFAULT_EMOJI: &str = "🚮";
+
+/// Emojis of the trash types the bot knew before they were stored with their metadata, used
+/// until the storage has been read and for rows without metadata. Their translated names and
+/// descriptions are in [`Strings::trash_types`].
+static BUILT_IN: [(&str, &str); 4] = [
+    ("Bioabfall", "🍂"),
+    ("Papier", "📰"),
+    ("Restmüll", "🗑"),
+    ("Wertstoff", "♻️"),
+];
+
+/// A trash type as stored in the `trash_types` table, with the metadata shown in messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashTypeInfo {
+    pub name: String,
+    pub emoji: Option<String>,
+    /// Colour of the bin, e.g. "braun".
+    pub colour: Option<String>,
+    /// Short description of what belongs into the bin, built-in types without a stored
+    /// description use a translated one.
+    pub description: Option<String>,
+}
+
+impl TrashTypeInfo {
+    /// A trash type without any metadata.
+    pub fn unknown(name: &str) -> Self {
+        TrashTypeInfo {
+            name: String::from(name),
+            emoji: None,
+            colour: None,
+            description: None,
+        }
+    }
+
+    fn built_in(name: &str) -> Option<Self> {
+        BUILT_IN
+            .iter()
+            .find(|(built_in, _)| *built_in == name)
+            .map(|(name, emoji)| TrashTypeInfo {
+                name: String::from(*name),
+                emoji: Some(String::from(*emoji)),
+                colour: None,
+                description: None,
+            })
+    }
+
+    /// Fills the metadata missing in the storage from the built-in metadata.
+    fn with_defaults(self) -> Self {
+        match TrashTypeInfo::built_in(&self.name) {
+            Some(built_in) => TrashTypeInfo {
+                emoji: self.emoji.or(built_in.emoji),
+                colour: self.colour.or(built_in.colour),
+                description: self.description,
+                name: self.name,
+            },
+            None => self,
+        }
+    }
+
+    /// The translated name with its emoji, e.g. "📰 Paper".
+    pub fn label(&self, text: &Strings) -> String {
+        format!(
+            "{} {}",
+            self.emoji.as_deref().unwrap_or(DEFAULT_EMOJI),
+            text.trash_type_name(&self.name)
+        )
+    }
+
+    /// The label followed by the bin colour and the description, if known, e.g.
+    /// "🍂 Bioabfall (braun): Küchen- und Gartenabfälle".
+    pub fn details(&self, text: &Strings) -> String {
+        let mut details = self.label(text);
+        if let Some(colour) = &self.colour {
+            details.push_str(&format!(" ({})", colour));
+        }
+        if let Some(description) = self
+            .description
+            .as_deref()
+            .or_else(|| text.trash_type_description(&self.name))
+        {
+            details.push_str(&format!(": {}", description));
+        }
+        details
+    }
+}
+
+/// The trash types known to the bot, loaded from the storage at startup and refreshed
+/// periodically, so new rows in `trash_types` are picked up without a restart.
+pub struct TrashTypeRegistry {
+    types: RwLock<Vec<TrashTypeInfo>>,
+}
+
+lazy_static! {
+    pub static ref REGISTRY: TrashTypeRegistry = TrashTypeRegistry {
+        types: RwLock::new(
+            BUILT_IN
+                .iter()
+                .filter_map(|(name, _)| TrashTypeInfo::built_in(name))
+                .collect()
+        ),
+    };
+}
+
+impl TrashTypeRegistry {
+    /// The trash type called `name`, without metadata if it isn't known (yet).
+    pub fn get(&self, name: &str) -> TrashTypeInfo {
+        self.types
+            .read()
+            .ok()
+            .and_then(|types| types.iter().find(|known| known.name == name).cloned())
+            .unwrap_or_else(|| TrashTypeInfo::unknown(name).with_defaults())
+    }
+
+    /// All known trash types, ordered by name.
+    pub fn all(&self) -> Vec<TrashTypeInfo> {
+        self.types
+            .read()
+            .map(|types| types.clone())
+            .unwrap_or_default()
+    }
+
+    /// Names of all known trash types, ordered by name.
+    pub fn names(&self) -> Vec<String> {
+        self.all().into_iter().map(|known| known.name).collect()
+    }
+
+    /// Replaces the known trash types with the ones in the storage, returns their number.
+    pub async fn refresh(&self, storage: &dyn Storage) -> Result<usize> {
+        let mut types: Vec<TrashTypeInfo> = storage
+            .get_trash_types()
+            .await?
+            .into_iter()
+            .map(TrashTypeInfo::with_defaults)
+            .collect();
+        types.sort_by(|a, b| a.name.cmp(&b.name));
+        let count = types.len();
+
+        match self.types.write() {
+            Ok(mut known) => *known = types,
+            Err(_) => log::error!("Trash type registry lock poisoned."),
+        }
+        Ok(count)
+    }
+
+    /// Refreshes the trash types every few minutes, failed refreshes keep the known ones.
+    pub async fn keep_refreshed(&self, storage: Arc<dyn Storage>) {
+        loop {
+            match self.refresh(storage.as_ref()).await {
+                Ok(count) => log::debug!("Loaded {} trash types.", count),
+                Err(e) => log::warn!("Could not load trash types: {}", e),
+            }
+
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::*;
+    use crate::bot_logic::strings::{ENGLISH, GERMAN};
+    use crate::storage::sqlite::SqliteStorage;
+    use crate::trash_dates::TrashType;
+
+    fn registry(types: Vec<TrashTypeInfo>) -> TrashTypeRegistry {
+        TrashTypeRegistry {
+            types: RwLock::new(types),
+        }
+    }
+
+    #[test]
+    fn unknown_type_falls_back_to_default_emoji() {
+        let glass = registry(Vec::new()).get("Glas");
+
+        assert_eq!(
+            TrashType::from("Glas"),
+            TrashType::Unknown(String::from("Glas"))
+        );
+        assert_eq!(glass, TrashTypeInfo::unknown("Glas"));
+        assert_eq!(glass.label(&ENGLISH), "🚮 Glas");
+        assert_eq!(glass.details(&ENGLISH), "🚮 Glas");
+    }
+
+    #[test]
+    fn built_in_type_without_storage_uses_translation() {
+        let organic = registry(Vec::new()).get("Bioabfall");
+
+        assert_eq!(organic.label(&ENGLISH), "🍂 Organic waste");
+        assert_eq!(
+            organic.details(&GERMAN),
+            "🍂 Bioabfall: Küchen- und Gartenabfälle"
+        );
+    }
+
+    #[test]
+    fn stored_metadata_overrides_built_in() {
+        let paper = TrashTypeInfo {
+            name: String::from("Papier"),
+            emoji: None,
+            colour: Some(String::from("blau")),
+            description: Some(String::from("Nur Karton")),
+        }
+        .with_defaults();
+
+        assert_eq!(paper.emoji.as_deref(), Some("📰"));
+        assert_eq!(paper.details(&GERMAN), "📰 Papier (blau): Nur Karton");
+    }
+
+    #[tokio::test]
+    async fn refresh_replaces_known_types() {
+        let path = std::env::temp_dir().join(format!("trash_types_{}.sqlite", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let storage = SqliteStorage::open(path).unwrap();
+        Connection::open(path)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO trash_types (name, emoji, colour, description)
+                 VALUES ('Glas', '🍾', 'grün', 'Flaschen und Gläser');
+                 UPDATE trash_types SET colour = 'braun' WHERE name = 'Bioabfall';",
+            )
+            .unwrap();
+        let registry = registry(vec![TrashTypeInfo::unknown("Sperrmüll")]);
+
+        let count = registry.refresh(&storage).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(count, 5);
+        assert_eq!(
+            registry.names(),
+            vec!["Bioabfall", "Glas", "Papier", "Restmüll", "Wertstoff"]
+        );
+        let organic = registry.get("Bioabfall");
+        assert_eq!(organic.emoji.as_deref(), Some("🍂"));
+        assert_eq!(organic.colour.as_deref(), Some("braun"));
+        assert_eq!(
+            registry.get("Glas").details(&GERMAN),
+            "🍾 Glas (grün): Flaschen und Gläser"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use std::convert::Infallible;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use carapax::types::Update;
+use carapax::UpdateHandler;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use tokio::sync::mpsc::{self, Sender};
+
+static DEFAULT_ADDRESS: &str = "0.0.0.0:8080";
+static DEFAULT_PATH: &str = "/";
+
+/// Header Telegram sends the `secret_token` of `setWebhook` in.
+static SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+/// Updates received but not handled yet, Telegram's requests wait once the queue is full.
+const UPDATE_QUEUE_SIZE: usize = 100;
+
+/// Where updates are received when Telegram delivers them by webhook instead of long polling.
+pub struct WebhookConfig {
+    pub address: SocketAddr,
+    pub path: String,
+    pub secret: Option<String>,
+}
+
+impl WebhookConfig {
+    /// Reads the webhook settings if `UPDATE_MODE` is `webhook`, `None` for long polling.
+    pub fn from_env() -> Option<Self> {
+        match env::var("UPDATE_MODE")
+            .unwrap_or_else(|_| String::from("longpoll"))
+            .as_str()
+        {
+            "longpoll" => None,
+            "webhook" => {
+                let address = env::var("WEBHOOK_ADDRESS")
+                    .unwrap_or_else(|_| String::from(DEFAULT_ADDRESS))
+                    .parse()
+                    .expect("WEBHOOK_ADDRESS is not a valid socket address");
+                let path = env::var("WEBHOOK_PATH").unwrap_or_else(|_| String::from(DEFAULT_PATH));
+                let secret = env::var("WEBHOOK_SECRET")
+                    .ok()
+                    .filter(|secret| !secret.is_empty());
+                if secret.is_none() {
+                    log::warn!("WEBHOOK_SECRET not set, accepting updates from anyone.");
+                }
+
+                Some(WebhookConfig {
+                    address,
+                    path,
+                    secret,
+                })
+            }
+            other => panic!(
+                "Unknown update mode '{}', set env variable 'UPDATE_MODE' to 'longpoll' or 'webhook'.",
+                other
+            ),
+        }
+    }
+
+    /// Compares the secret without returning early, so the response time doesn't tell how much
+    /// of a guessed secret was correct.
+    fn accepts(&self, request: &Request<Body>) -> bool {
+        let secret = match &self.secret {
+            Some(secret) => secret.as_bytes(),
+            None => return true,
+        };
+        let token = match request.headers().get(SECRET_TOKEN_HEADER) {
+            Some(token) => token.as_bytes(),
+            None => return false,
+        };
+
+        token.len() == secret.len()
+            && token
+                .iter()
+                .zip(secret.iter())
+                .fold(0, |difference, (a, b)| difference | (a ^ b))
+                == 0
+    }
+}
+
+fn respond(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+async fn handle_request(
+    config: Arc<WebhookConfig>,
+    updates: Sender<Update>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() != config.path {
+        return Ok(respond(StatusCode::NOT_FOUND));
+    }
+    if request.method() != Method::POST {
+        return Ok(respond(StatusCode::METHOD_NOT_ALLOWED));
+    }
+    if !config.accepts(&request) {
+        log::warn!("Rejected webhook request with missing or wrong secret token.");
+        return Ok(respond(StatusCode::UNAUTHORIZED));
+    }
+
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            log::warn!("Could not read webhook request: {}", e);
+            return Ok(respond(StatusCode::BAD_REQUEST));
+        }
+    };
+    let update: Update = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            log::warn!("Could not parse update: {}", e);
+            return Ok(respond(StatusCode::BAD_REQUEST));
+        }
+    };
+
+    // Telegram only needs to know the update arrived, handling it may take a while and would
+    // make Telegram resend it after a timeout.
+    if updates.send(update).await.is_err() {
+        log::error!("Update handler stopped, dropping update.");
+        return Ok(respond(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    Ok(respond(StatusCode::OK))
+}
+
+/// Passes the received updates to `handler` one after another, like with long polling.
+async fn handle_updates<H>(mut handler: H, mut updates: mpsc::Receiver<Update>)
+where
+    H: UpdateHandler,
+{
+    while let Some(update) = updates.recv().await {
+        handler.handle(update).await;
+    }
+}
+
+/// Receives updates POSTed by Telegram (or by hand, e.g. recorded `Update` json sent with curl)
+/// and passes them to `handler`. Runs until the server fails.
+pub async fn run<H>(config: WebhookConfig, handler: H)
+where
+    H: UpdateHandler + Send + 'static,
+{
+    let address = config.address;
+    log::info!(
+        "Listening for webhook updates on {}{}",
+        address,
+        config.path
+    );
+    let config = Arc::new(config);
+    let (sender, receiver) = mpsc::channel(UPDATE_QUEUE_SIZE);
+    tokio::spawn(handle_updates(handler, receiver));
+
+    let make_service = make_service_fn(move |_| {
+        let config = config.clone();
+        let sender = sender.clone();
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(config.clone(), sender.clone(), request)
+            }))
+        }
+    });
+
+    if let Err(e) = Server::bind(&address).serve(make_service).await {
+        log::error!("Webhook server failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static UPDATE: &str = include_str!("../../fixtures/update.json");
+
+    fn config() -> Arc<WebhookConfig> {
+        Arc::new(WebhookConfig {
+            address: DEFAULT_ADDRESS.parse().unwrap(),
+            path: String::from("/telegram"),
+            secret: Some(String::from("secret")),
+        })
+    }
+
+    fn request(path: &str, secret: Option<&str>) -> Request<Body> {
+        let mut request = Request::post(path);
+        if let Some(secret) = secret {
+            request = request.header(SECRET_TOKEN_HEADER, secret);
+        }
+        request.body(Body::from(UPDATE)).unwrap()
+    }
+
+    async fn status(request: Request<Body>, updates: Sender<Update>) -> StatusCode {
+        handle_request(config(), updates, request)
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn accepts_update_with_secret() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let status = status(request("/telegram", Some("secret")), sender).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(receiver.recv().await.unwrap().id, 10000);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_or_wrong_secret() {
+        let (sender, mut receiver) = mpsc::channel(2);
+
+        let missing = status(request("/telegram", None), sender.clone()).await;
+        let wrong = status(request("/telegram", Some("secreT")), sender).await;
+
+        assert_eq!(missing, StatusCode::UNAUTHORIZED);
+        assert_eq!(wrong, StatusCode::UNAUTHORIZED);
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_path() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let status = status(request("/", Some("secret")), sender).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(receiver.recv().await.is_none());
+    }
+}